@@ -0,0 +1,22 @@
+#!/bin/sh
+# Map the action `mode` input to a patch-release-me subcommand
+set -e
+
+mode="${1:-display}"
+shift || true
+
+case "$mode" in
+  display | check | sync)
+    exec patch-release-me --non-interactive "$@" "$mode"
+    ;;
+  bump)
+    exec patch-release-me --non-interactive "$@" bump
+    ;;
+  major | minor | patch)
+    exec patch-release-me --non-interactive "$@" bump "--$mode"
+    ;;
+  *)
+    echo "::error title=patch-release-me::Unknown mode '$mode' (expected display, check, sync, bump, major, minor or patch)"
+    exit 1
+    ;;
+esac
@@ -0,0 +1,255 @@
+//! Coverage audit of the version strings in a project.
+//!
+//! Finds occurrences of the current version (`1.2.3`, `v1.2.3`) and its
+//! `major.minor` variant (`1.2`) which are not captured by any location, and
+//! suggests location entries which would cover them.
+use anyhow::Result;
+use log::debug;
+use regex::Regex;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::config::{LocationPattern, is_excluded};
+
+lazy_static::lazy_static! {
+    /// Version like token (`1.2`, `v1.2.3`, `1.2.3-rc.1`)
+    static ref VERSION_TOKEN: Regex =
+        Regex::new(r"v?[0-9]+(?:\.[0-9]+)+(?:-[0-9A-Za-z][0-9A-Za-z.-]*)?").unwrap();
+}
+
+/// Files larger than this are not searched
+const MAX_FILE_SIZE: u64 = 1024 * 1024;
+/// Maximum length of the text before a version used in a suggested pattern
+const MAX_PREFIX_LENGTH: usize = 32;
+
+/// Version string which is not captured by any location
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Uncovered {
+    /// File the version was found in
+    pub path: PathBuf,
+    /// Line (1-based) of the version
+    pub line: usize,
+    /// Version string as found in the file (`v1.2.3`, `1.2`)
+    pub value: String,
+    /// Regex pattern which would capture the version
+    pub pattern: String,
+}
+
+/// Search the files of a project for versions not covered by the captured spans
+pub fn find_uncovered(
+    root: &Path,
+    versions: &[semver::Version],
+    excludes: &[String],
+    covered: &[(PathBuf, Range<usize>)],
+) -> Result<Vec<Uncovered>> {
+    let mut uncovered = Vec::new();
+    for path in project_files(root)? {
+        if is_excluded(excludes, root, &path) {
+            debug!("Excluded: {:?}", path);
+            continue;
+        }
+        if std::fs::metadata(&path).map_or(true, |m| m.len() > MAX_FILE_SIZE) {
+            continue;
+        }
+        // Binary and non UTF-8 files are skipped
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+
+        let spans: Vec<&Range<usize>> = covered
+            .iter()
+            .filter(|(covered_path, _)| covered_path == &path)
+            .map(|(_, span)| span)
+            .collect();
+        for found in search(&content, versions) {
+            if spans
+                .iter()
+                .any(|span| span.start < found.1.end && found.1.start < span.end)
+            {
+                continue;
+            }
+            uncovered.push(Uncovered {
+                path: path.clone(),
+                line: content[..found.1.start].matches('\n').count() + 1,
+                value: content[found.1.clone()].to_string(),
+                pattern: found.0,
+            });
+        }
+    }
+    Ok(uncovered)
+}
+
+/// Suggested location per file covering the uncovered versions
+pub fn suggest_locations(root: &Path, uncovered: &[Uncovered]) -> Vec<LocationPattern> {
+    let mut locations: Vec<LocationPattern> = Vec::new();
+    for item in uncovered {
+        let path = item.path.strip_prefix(root).unwrap_or(&item.path);
+        let name = path.display().to_string();
+        let index = match locations.iter().position(|l| l.name == name) {
+            Some(index) => index,
+            None => {
+                locations.push(LocationPattern {
+                    name,
+                    paths: vec![path.to_path_buf()],
+                    ..Default::default()
+                });
+                locations.len() - 1
+            }
+        };
+        let patterns = &mut locations[index].patterns;
+        if !patterns
+            .iter()
+            .any(|pattern| pattern.as_str() == item.pattern)
+        {
+            patterns.push(item.pattern.clone().into());
+        }
+    }
+    locations
+}
+
+/// Versions in the content, with a suggested pattern and their span
+fn search(content: &str, versions: &[semver::Version]) -> Vec<(String, Range<usize>)> {
+    let mut found = Vec::new();
+    for token in VERSION_TOKEN.find_iter(content) {
+        // Part of a larger word (`py3.11`, `x.1.2`)
+        if content[..token.start()]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '.')
+        {
+            continue;
+        }
+        let value = token.as_str().trim_start_matches('v');
+        let placeholder = if versions.iter().any(|v| v.to_string() == value) {
+            "{version}"
+        } else if versions
+            .iter()
+            .any(|v| format!("{}.{}", v.major, v.minor) == value)
+        {
+            r"(?P<major>[0-9]+)\.(?P<minor>[0-9]+)"
+        } else {
+            continue;
+        };
+
+        let prefix = suggestion_prefix(content, token.start());
+        let v = if token.as_str().starts_with('v') {
+            "v"
+        } else {
+            ""
+        };
+        found.push((
+            format!("{}{v}{placeholder}", regex::escape(prefix)),
+            token.range(),
+        ));
+    }
+    found
+}
+
+/// Text on the line before a version used to anchor a suggested pattern
+fn suggestion_prefix(content: &str, start: usize) -> &str {
+    let line_start = content[..start].rfind('\n').map_or(0, |i| i + 1);
+    let prefix = content[line_start..start].trim_start();
+    match prefix.char_indices().rev().nth(MAX_PREFIX_LENGTH - 1) {
+        Some((index, _)) => &prefix[index..],
+        None => prefix,
+    }
+}
+
+/// Files of the project, files ignored by Git are skipped when the project is
+/// a Git repository
+fn project_files(root: &Path) -> Result<Vec<PathBuf>> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args([
+            "ls-files",
+            "-z",
+            "--cached",
+            "--others",
+            "--exclude-standard",
+        ])
+        .output();
+    if let Ok(output) = output {
+        if output.status.success() {
+            let mut files: Vec<PathBuf> = String::from_utf8_lossy(&output.stdout)
+                .split('\0')
+                .filter(|path| !path.is_empty())
+                .map(|path| root.join(path))
+                .filter(|path| path.is_file())
+                .collect();
+            files.sort();
+            files.dedup();
+            return Ok(files);
+        }
+    }
+    debug!("Not a Git repository, searching all files in {:?}", root);
+
+    let mut files = Vec::new();
+    let mut directories = vec![root.to_path_buf()];
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(&directory)? {
+            let path = entry?.path();
+            if path.file_name().is_some_and(|name| name == ".git") {
+                continue;
+            }
+            if path.is_dir() {
+                directories.push(path);
+            } else {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_search() {
+        let versions = vec![semver::Version::new(1, 2, 3)];
+        let content =
+            "image: ghcr.io/acme/api:v1.2.3\nDocs for 1.2.x\nPython 3.11, 1.2.30, 11.2.3\n";
+        let found: Vec<(String, &str)> = search(content, &versions)
+            .into_iter()
+            .map(|(pattern, range)| (pattern, &content[range]))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (r"image: ghcr\.io/acme/api:v{version}".to_string(), "v1.2.3"),
+                (
+                    r"Docs for (?P<major>[0-9]+)\.(?P<minor>[0-9]+)".to_string(),
+                    "1.2"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_suggest_locations() {
+        let uncovered = |line: usize, pattern: &str| Uncovered {
+            path: PathBuf::from("/repo/docs/install.md"),
+            line,
+            value: "1.2.3".to_string(),
+            pattern: pattern.to_string(),
+        };
+        let locations = suggest_locations(
+            Path::new("/repo"),
+            &[
+                uncovered(1, "api:{version}"),
+                uncovered(4, "api:{version}"),
+                uncovered(9, "tag {version}"),
+            ],
+        );
+        assert_eq!(locations.len(), 1);
+        assert_eq!(locations[0].name, "docs/install.md");
+        assert_eq!(locations[0].paths, vec![PathBuf::from("docs/install.md")]);
+        assert_eq!(
+            locations[0].patterns,
+            vec!["api:{version}", "tag {version}"]
+        );
+    }
+}
@@ -0,0 +1,424 @@
+//! Bump of the project root and its packages.
+//!
+//! Packages are bumped after their dependencies, with `--cascade` the packages
+//! depending on a bumped package get a patch bump too. The hooks run around
+//! the bump and the patched files are rolled back when the bump fails before
+//! it is complete.
+use anyhow::Result;
+use console::style;
+use log::warn;
+use std::path::{Path, PathBuf};
+
+use crate::cli::{ArgumentCommands, Arguments};
+use crate::config::{BumpMode, Config, Target};
+use crate::dependencies::DependencyGraph;
+use crate::github::{self, ActionReport};
+use crate::hooks::HookStage;
+use crate::interactive::{confirm_persist_excludes, new_version};
+use crate::rollback::Guard;
+use crate::tui;
+use crate::workflows::{Summary, Workflow, WorkflowMode};
+
+/// Bump the targets, `mode` is the bump of the first target
+pub async fn run(
+    arguments: &Arguments,
+    config: &Config,
+    targets: &[Target],
+    stream: Option<&str>,
+    mode: &WorkflowMode,
+    review: bool,
+    multiple: bool,
+) -> Result<()> {
+    let cascade = matches!(
+        arguments.commands,
+        Some(ArgumentCommands::Bump { cascade: true, .. })
+    );
+    let interactive = matches!(
+        arguments.commands,
+        Some(ArgumentCommands::Bump {
+            interactive: true,
+            ..
+        })
+    );
+    let review = review
+        || matches!(
+            arguments.commands,
+            Some(ArgumentCommands::Bump { tui: true, .. })
+        );
+    let commit = matches!(
+        arguments.commands,
+        Some(ArgumentCommands::Bump { commit: true, .. })
+    );
+
+    // Packages are bumped after their dependencies
+    let packages: Vec<Target> = config
+        .targets(&arguments.root, None)?
+        .into_iter()
+        .filter(|target| target.package.is_some())
+        .collect();
+    let graph = DependencyGraph::load(&packages)?;
+    let ordered = order(targets, &packages, &graph, cascade)?;
+
+    // Check the expected matches of every location before writing
+    for target in &ordered {
+        Workflow::init()
+            .root(target.root.clone())?
+            .date(arguments.date)
+            .current(target.config.stream_version(stream)?.cloned())
+            .locations(target.config.stream_locations(stream))?
+            .build()
+            .validate()?;
+    }
+
+    let old_version = targets
+        .first()
+        .and_then(|t| t.config.stream_version(stream).ok().flatten())
+        .cloned()
+        .unwrap_or_default();
+    let next_version = match mode {
+        WorkflowMode::Bump { version, .. } => version.to_string(),
+        _ => String::new(),
+    };
+    // Patched files are restored when the bump fails before it is
+    // committed
+    let guard = Guard::new();
+    config.hooks.run(
+        HookStage::PreBump,
+        &arguments.root,
+        &hook_env(&arguments.root, &old_version, &next_version, None),
+    )?;
+
+    let mut summaries = Vec::new();
+    let mut excluded: Vec<(PathBuf, Vec<(String, PathBuf)>)> = Vec::new();
+    let mut bumped: Vec<String> = Vec::new();
+    let mut requirement_files: Vec<PathBuf> = Vec::new();
+    for target in ordered {
+        let explicit = targets.iter().any(|t| t.package == target.package);
+        let target_mode = if !explicit {
+            // Cascade a patch bump to packages with a bumped dependency
+            let package = target.package.as_deref().unwrap_or_default();
+            if !graph
+                .dependencies(package)
+                .iter()
+                .any(|dependency| bumped.iter().any(|b| b == dependency))
+            {
+                continue;
+            }
+            WorkflowMode::Bump {
+                mode: BumpMode::Patch,
+                version: new_version(&target.config, None, &BumpMode::Patch)?,
+                counter: target.config.counter.map(|c| c + 1),
+            }
+        } else if targets.first().map(|t| &t.package) == Some(&target.package) {
+            mode.clone()
+        } else {
+            crate::command_mode(arguments, &target.root, &target.config, stream)?
+        };
+        let WorkflowMode::Bump {
+            mode: bump_mode,
+            version,
+            ..
+        } = &target_mode
+        else {
+            continue;
+        };
+
+        if let Some(package) = &target.package {
+            println!(
+                "\n{} Package: {}",
+                style("📦").cyan(),
+                style(package).bold()
+            );
+        }
+        match stream {
+            Some(stream) => println!(
+                "\n{} Bumping version ({}): {:?}",
+                style("→").cyan(),
+                style(stream).cyan().bold(),
+                bump_mode
+            ),
+            None => println!("\n{} Bumping version: {:?}", style("→").cyan(), bump_mode),
+        }
+        println!("{}", style("─".repeat(60)).dim());
+
+        // Hooks of the project run around the whole bump, the hooks of
+        // a package around the bump of the package
+        let package_hooks = target.package.as_ref().map(|_| &target.config.hooks);
+        let package_old_version = target
+            .config
+            .stream_version(stream)?
+            .cloned()
+            .unwrap_or_default();
+        let start = guard.journal().len();
+        if let Some(hooks) = package_hooks {
+            hooks.run(
+                HookStage::PreBump,
+                &target.root,
+                &hook_env(
+                    &target.root,
+                    &package_old_version,
+                    &version.to_string(),
+                    None,
+                ),
+            )?;
+        }
+
+        let workflow = Workflow::init()
+            .root(target.root.clone())?
+            .mode(target_mode.clone())
+            .date(arguments.date)
+            .interactive(interactive)
+            .current(Some(package_old_version.clone()).filter(|v| !v.is_empty()))
+            .locations(target.config.stream_locations(stream))?
+            .build();
+        let summary = if review {
+            match tui::review(&workflow, guard.journal())? {
+                Some(summary) => summary,
+                // Nothing was written, so dependents are not bumped
+                None => continue,
+            }
+        } else {
+            workflow.patch(guard.journal()).await?
+        };
+
+        if !summary.excluded.is_empty() {
+            let config_path = match target.package {
+                Some(_) => target.root.join(".release.yml"),
+                None => arguments.root.join(&arguments.config),
+            };
+            excluded.push((config_path, summary.excluded.clone()));
+        }
+
+        // Update the requirements of dependent packages
+        if let (Some(package), None) = (&target.package, stream) {
+            for update in graph.update_requirements(package, version, guard.journal())? {
+                requirement_files.push(update.path.clone());
+                println!(
+                    "  {} {} ({}): {} {} {}",
+                    style("🔗").cyan(),
+                    style(update.path.display()).bold(),
+                    style(&update.dependent).cyan(),
+                    style(&update.old).red(),
+                    style("→").dim(),
+                    style(&update.new).green().bold()
+                );
+            }
+            bumped.push(package.clone());
+        }
+        if let Some(hooks) = package_hooks {
+            let changed = guard.journal().files_since(start);
+            hooks.run(
+                HookStage::PostBump,
+                &target.root,
+                &hook_env(
+                    &target.root,
+                    &package_old_version,
+                    &version.to_string(),
+                    Some(&changed),
+                ),
+            )?;
+        }
+        summaries.push((target, Some(version.to_string()), summary));
+    }
+
+    let changed = guard.journal().files_since(0);
+    config.hooks.run(
+        HookStage::PostBump,
+        &arguments.root,
+        &hook_env(&arguments.root, &old_version, &next_version, Some(&changed)),
+    )?;
+
+    if multiple {
+        println!();
+        crate::print_packages_summary(&summaries);
+    }
+
+    if commit && changed.is_empty() {
+        println!(
+            "  {} No files changed, nothing to commit",
+            style("ℹ").blue()
+        );
+    }
+    let commit = commit && !changed.is_empty();
+    if commit {
+        // Files staged by the hooks (`git add Cargo.lock`) are
+        // committed with the patched files
+        commit_changes(&arguments.root, &changed, &next_version)?;
+        println!(
+            "{} Committed {} files",
+            style("✓").green(),
+            style(changed.len()).bold()
+        );
+    }
+    // The bump is complete, a failing post commit hook keeps the files
+    guard.disarm();
+
+    if commit {
+        let env = hook_env(&arguments.root, &old_version, &next_version, Some(&changed));
+        config
+            .hooks
+            .run(HookStage::PostCommit, &arguments.root, &env)?;
+        for (target, version, summary) in &summaries {
+            if target.package.is_some() {
+                target.config.hooks.run(
+                    HookStage::PostCommit,
+                    &target.root,
+                    &hook_env(
+                        &target.root,
+                        target
+                            .config
+                            .stream_version(stream)?
+                            .map_or("", |v| v.as_str()),
+                        version.as_deref().unwrap_or_default(),
+                        Some(&summary.files),
+                    ),
+                )?;
+            }
+        }
+    }
+
+    if github::is_actions() {
+        report(arguments, targets, stream, &summaries, &requirement_files)?;
+    }
+    save_excludes(excluded)?;
+
+    println!();
+    println!("{}", style("━".repeat(60)).dim());
+    println!(
+        "{} Version bump completed successfully!",
+        style("✓").green().bold()
+    );
+    println!("{}", style("━".repeat(60)).dim());
+    println!();
+    Ok(())
+}
+
+/// Environment of a hook, changed files are relative to the root of the hook
+fn hook_env<'a>(
+    root: &Path,
+    old_version: &str,
+    new_version: &str,
+    changed: Option<&[PathBuf]>,
+) -> Vec<(&'a str, String)> {
+    let mut env = vec![
+        ("PRM_OLD_VERSION", old_version.to_string()),
+        ("PRM_NEW_VERSION", new_version.to_string()),
+    ];
+    if let Some(changed) = changed {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let files: Vec<String> = changed
+            .iter()
+            .map(|path| {
+                path.strip_prefix(&root)
+                    .unwrap_or(path)
+                    .display()
+                    .to_string()
+            })
+            .collect();
+        env.push(("PRM_CHANGED_FILES", files.join("\n")));
+    }
+    env
+}
+
+/// Commit the changed files with Git
+fn commit_changes(root: &Path, files: &[PathBuf], version: &str) -> Result<()> {
+    let git = |args: &[&std::ffi::OsStr]| -> Result<()> {
+        let status = std::process::Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(args)
+            .status()?;
+        if !status.success() {
+            anyhow::bail!("git {:?} failed with {}", args.first(), status);
+        }
+        Ok(())
+    };
+    let mut add: Vec<&std::ffi::OsStr> = vec!["add".as_ref(), "--".as_ref()];
+    add.extend(files.iter().map(|path| path.as_os_str()));
+    git(&add)?;
+    let message = format!("Bump version to {version}");
+    git(&["commit".as_ref(), "-m".as_ref(), message.as_ref()])
+}
+
+/// Targets in the order they are bumped: the project root, then the packages
+/// after their dependencies. With `cascade` every package is included, so
+/// dependents of a bumped package can be bumped too.
+fn order<'a>(
+    targets: &'a [Target],
+    packages: &'a [Target],
+    graph: &DependencyGraph,
+    cascade: bool,
+) -> Result<Vec<&'a Target>> {
+    let mut ordered: Vec<&Target> = targets
+        .iter()
+        .filter(|target| target.package.is_none())
+        .collect();
+    for package in graph.topological()? {
+        let target = targets
+            .iter()
+            .chain(packages.iter().filter(|_| cascade))
+            .find(|target| target.package.as_ref() == Some(&package));
+        if let Some(target) = target {
+            ordered.push(target);
+        }
+    }
+    Ok(ordered)
+}
+
+/// Write the outputs of the bump for GitHub Actions
+fn report(
+    arguments: &Arguments,
+    targets: &[Target],
+    stream: Option<&str>,
+    summaries: &[(&Target, Option<String>, Summary)],
+    requirement_files: &[PathBuf],
+) -> Result<()> {
+    let mut changed_files: Vec<PathBuf> = Vec::new();
+    for path in summaries
+        .iter()
+        .flat_map(|(_, _, summary)| summary.files.iter())
+        .chain(requirement_files.iter())
+    {
+        if !changed_files.contains(path) {
+            changed_files.push(path.clone());
+        }
+    }
+    ActionReport {
+        command: match arguments.commands {
+            Some(ArgumentCommands::Sync { .. }) => "sync".to_string(),
+            _ => "bump".to_string(),
+        },
+        old_version: targets
+            .first()
+            .and_then(|t| t.config.stream_version(stream).ok().flatten())
+            .cloned(),
+        new_version: summaries
+            .first()
+            .and_then(|(_, version, _)| version.clone()),
+        changed_files,
+        drift: None,
+    }
+    .write(&arguments.root)
+}
+
+/// Save the excludes chosen while reviewing
+fn save_excludes(excluded: Vec<(PathBuf, Vec<(String, PathBuf)>)>) -> Result<()> {
+    for (config_path, excludes) in excluded {
+        if !config_path.exists() {
+            warn!(
+                "Excludes not saved, configuration file not found: {}",
+                config_path.display()
+            );
+            continue;
+        }
+        if confirm_persist_excludes(&excludes)? {
+            Config::persist_excludes(&config_path, &excludes)?;
+            println!(
+                "{} Excludes saved to {}",
+                style("✓").green(),
+                style(config_path.display()).cyan()
+            );
+        }
+    }
+    Ok(())
+}
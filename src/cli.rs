@@ -1,8 +1,10 @@
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand};
 use console::style;
 use std::io::Write;
 use std::path::PathBuf;
 
+use crate::config::BumpMode;
+
 pub const VERSION_NUMBER: &str = env!("CARGO_PKG_VERSION");
 pub const AUTHOR: &str = env!("CARGO_PKG_AUTHORS");
 
@@ -26,6 +28,10 @@ pub struct Arguments {
     #[clap(long, env, default_value_t = false)]
     pub debug: bool,
 
+    /// Never prompt for input (enabled when stdin is not a terminal or `CI=true`)
+    #[clap(long, env = "PRM_NON_INTERACTIVE", default_value_t = false)]
+    pub non_interactive: bool,
+
     /// Hide the ASCII banner on startup
     #[clap(long, default_value_t = false)]
     pub disable_banner: bool,
@@ -38,6 +44,18 @@ pub struct Arguments {
     #[clap(short, long, env, default_value = ".release.yml")]
     pub config: PathBuf,
 
+    /// Additional defaults file(s), applied on top of the built-in defaults
+    #[clap(long, env = "PRM_DEFAULTS", value_delimiter = ',')]
+    pub defaults: Vec<PathBuf>,
+
+    /// Set or override a configuration variable (e.g. `--var image=ghcr.io/acme/api`)
+    #[clap(long = "var", value_name = "KEY=VALUE", value_parser = parse_variable)]
+    pub variables: Vec<(String, String)>,
+
+    /// Release date for date, year and timestamp locations (e.g. `2025-01-31`)
+    #[clap(long, env = "PRM_DATE", value_parser = parse_date)]
+    pub date: Option<chrono::DateTime<chrono::Utc>>,
+
     /// Subcommands
     #[clap(subcommand)]
     pub commands: Option<ArgumentCommands>,
@@ -65,6 +83,10 @@ pub enum ArgumentCommands {
         )]
         language_ecosystems: Vec<String>,
 
+        /// Detect ecosystems from the files in the project root
+        #[clap(long, default_value = "false", help = "Detect project ecosystems")]
+        detect: bool,
+
         /// Include default patterns for common files
         #[clap(
             short,
@@ -78,31 +100,84 @@ pub enum ArgumentCommands {
 
     /// Show what files and versions would be updated (dry-run)
     #[command(about = "Preview changes without modifying files")]
-    Display,
+    Display {
+        /// Only display locations of this version stream
+        #[clap(long, help = "Version stream to display")]
+        stream: Option<String>,
+
+        /// Only display this package (from `packages` in the configuration)
+        #[clap(long, help = "Package to display")]
+        package: Option<String>,
+    },
+
+    /// Check that all tracked files use the version in .release.yml
+    #[command(about = "Verify tracked files match the current version")]
+    Check {
+        /// Only check locations of this version stream
+        #[clap(long, help = "Version stream to check")]
+        stream: Option<String>,
+
+        /// Only check this package (from `packages` in the configuration)
+        #[clap(long, help = "Package to check")]
+        package: Option<String>,
+
+        /// Write a SARIF 2.1.0 report of the drift to a file
+        #[clap(long, value_name = "FILE", help = "Write a SARIF report")]
+        sarif: Option<PathBuf>,
+
+        /// Write a JUnit XML report (one test case per location) to a file
+        #[clap(long, value_name = "FILE", help = "Write a JUnit XML report")]
+        junit: Option<PathBuf>,
+    },
+
+    /// Report if the versions in all tracked files agree
+    #[command(about = "Show a version consistency report of tracked files")]
+    Status {
+        /// Only report locations of this version stream
+        #[clap(long, help = "Version stream to report")]
+        stream: Option<String>,
+
+        /// Only report this package (from `packages` in the configuration)
+        #[clap(long, help = "Package to report")]
+        package: Option<String>,
+    },
+
+    /// Find versions in the project which no location covers
+    #[command(about = "Find version strings not covered by any location")]
+    Audit,
 
     /// Sync all files to the current version in .release.yml
     #[command(about = "Apply current version to all tracked files")]
-    Sync,
+    Sync {
+        /// Only sync this package (from `packages` in the configuration)
+        #[clap(long, help = "Package to sync")]
+        package: Option<String>,
+    },
 
     /// Bump version and update all tracked files
-    #[command(about = "Increment version and update files")]
+    #[command(
+        about = "Increment version and update files",
+        group = ArgGroup::new("bump_mode")
+            .args(["set_version", "mode", "patch", "minor", "major"])
+            .multiple(false)
+    )]
     Bump {
         /// Manually set a specific version (e.g., 1.2.3)
         #[clap(
             short,
             long,
             env,
-            default_value = "",
+            value_parser = parse_version,
             help = "Specify exact version to set"
         )]
-        set_version: String,
+        set_version: Option<semver::Version>,
 
         /// Bump mode: major, minor, or patch
-        #[clap(short, long, env, help = "Choose: major, minor, or patch")]
-        mode: Option<String>,
+        #[clap(short, long, env, value_enum, help = "Choose: major, minor, or patch")]
+        mode: Option<BumpMode>,
 
-        /// Increment patch version (x.x.N+1) - default if no flags set
-        #[clap(long, default_value = "true", help = "Bump patch version (default)")]
+        /// Increment patch version (x.x.N+1)
+        #[clap(long, default_value = "false", help = "Bump patch version")]
         patch: bool,
 
         /// Increment minor version (x.N+1.0)
@@ -112,9 +187,62 @@ pub enum ArgumentCommands {
         /// Increment major version (N+1.0.0)
         #[clap(long, default_value = "false", help = "Bump major version")]
         major: bool,
+
+        /// Version stream to bump (from `versions` in the configuration)
+        #[clap(long, help = "Version stream to bump")]
+        stream: Option<String>,
+
+        /// Only bump this package (from `packages` in the configuration)
+        #[clap(long, help = "Package to bump")]
+        package: Option<String>,
+
+        /// Review every replacement before it is applied
+        #[clap(short, long, default_value = "false", help = "Approve each change")]
+        interactive: bool,
+
+        /// Review all locations, files and matches in a full-screen UI
+        #[clap(
+            long,
+            default_value = "false",
+            conflicts_with = "interactive",
+            help = "Review changes in a full-screen UI"
+        )]
+        tui: bool,
+
+        /// Also bump (patch) packages which depend on a bumped package
+        #[clap(long, default_value = "false", help = "Cascade bumps to dependents")]
+        cascade: bool,
+
+        /// Commit the changed files and run the `post_commit` hooks
+        #[clap(long, default_value = "false", help = "Commit the changed files")]
+        commit: bool,
     },
 }
 
+/// Parse a `key=value` variable argument
+fn parse_variable(value: &str) -> Result<(String, String), String> {
+    value
+        .split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+        .filter(|(key, _)| !key.is_empty())
+        .ok_or_else(|| format!("Invalid variable `{value}`, expected KEY=VALUE"))
+}
+
+/// Parse a semantic version (e.g. `1.2.3`)
+fn parse_version(value: &str) -> Result<semver::Version, String> {
+    semver::Version::parse(value).map_err(|e| format!("Invalid version `{value}`: {e}"))
+}
+
+/// Parse a release date as either `YYYY-MM-DD` or an RFC 3339 timestamp
+fn parse_date(value: &str) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    if let Ok(date) = chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_time(chrono::NaiveTime::MIN).and_utc());
+    }
+    chrono::DateTime::parse_from_rfc3339(value)
+        .map(|date| date.with_timezone(&chrono::Utc))
+        .map_err(|e| format!("Invalid date `{value}`: {e}"))
+}
+
 pub fn init() -> Arguments {
     let arguments = Arguments::parse();
 
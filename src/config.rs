@@ -1,10 +1,30 @@
 use anyhow::Result;
-use log::{debug, info};
+use log::{debug, info, warn};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::{fmt::Display, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
 
 use crate::defaults::Defaults;
+use crate::error::Error;
+use crate::hooks::Hooks;
+use crate::scripts::Script;
+use crate::yaml;
+
+/// Prefix for environment variables which override configuration variables
+pub const VARIABLE_ENV_PREFIX: &str = "PRM_VAR_";
+/// Environment variable for the directory of preset configuration files
+pub const PRESETS_ENV: &str = "PRM_PRESETS";
+/// Prefix used in `extends` to load a preset by name
+pub const PRESET_PREFIX: &str = "preset:";
+
+lazy_static::lazy_static! {
+    /// Placeholder reference inside a pattern or variable (`{name}`)
+    static ref PLACEHOLDER: Regex = Regex::new(r"\{([A-Za-z0-9_-]+)\}").unwrap();
+}
 
 /// Bump mode for the version
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -22,18 +42,34 @@ pub enum BumpMode {
 /// Configuration settings
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+    /// Base configurations / presets this configuration extends
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>,
+
     /// Name of the project
     #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     /// Repository of the project
     #[serde(skip_serializing_if = "Option::is_none")]
     pub repository: Option<String>,
+    /// Host of the repository (`github.com`, `gitlab.example.com`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
     /// Version to set
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
+    /// Named version streams which are versioned independently (`api: 3.0.0`)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub versions: BTreeMap<String, String>,
     /// If Default locations should be used or not
     #[serde(skip_serializing_if = "Option::is_none")]
     pub default: Option<bool>,
+    /// Select and override individual default locations
+    #[serde(default, skip_serializing_if = "DefaultsConfig::is_empty")]
+    pub defaults: DefaultsConfig,
+    /// Build number / counter, incremented on every bump
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub counter: Option<u64>,
 
     /// Ecosystem to use for the project
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -46,35 +82,224 @@ pub struct Config {
     #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
     pub excludes: Vec<String>,
 
+    /// User defined placeholders (`{key}`) used in patterns
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub variables: BTreeMap<String, Variable>,
+
     /// Update versions in these locations
     #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
     pub locations: Vec<LocationPattern>,
+
+    /// Packages in a monorepo, each with their own version and locations
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub packages: BTreeMap<String, Package>,
+
+    /// Shell commands run before and after bumping
+    #[serde(default, skip_serializing_if = "Hooks::is_empty")]
+    pub hooks: Hooks,
+}
+
+/// Selection of default locations and overrides by location name
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DefaultsConfig {
+    /// Only use these default locations (all if empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    /// Default locations to ignore
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Overrides for default locations by name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub overrides: BTreeMap<String, DefaultOverride>,
+}
+
+/// Override for a single default location
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DefaultOverride {
+    /// Restrict the location to these paths
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub paths: Vec<PathBuf>,
+    /// Additional excludes for the location
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excludes: Vec<String>,
+}
+
+impl DefaultsConfig {
+    /// If no defaults selection or overrides are set
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty() && self.overrides.is_empty()
+    }
+
+    /// Apply the selection and overrides to the default locations
+    pub fn apply(&self, locations: Vec<LocationPattern>) -> Vec<LocationPattern> {
+        // Typos would silently select or override nothing
+        let known: Vec<&str> = locations.iter().map(|loc| loc.name.as_str()).collect();
+        let selected = [("include", &self.include), ("exclude", &self.exclude)];
+        let names = selected
+            .iter()
+            .flat_map(|(field, names)| names.iter().map(move |name| (*field, name)))
+            .chain(self.overrides.keys().map(|name| ("overrides", name)));
+        for (field, name) in names {
+            if known.contains(&name.as_str()) {
+                continue;
+            }
+            match nearest_name(name, &known) {
+                Some(nearest) => warn!(
+                    "Unknown default location `{}` in `defaults.{}`, did you mean `{}`?",
+                    name, field, nearest
+                ),
+                None => warn!(
+                    "Unknown default location `{}` in `defaults.{}`",
+                    name, field
+                ),
+            }
+        }
+
+        locations
+            .into_iter()
+            .filter(|loc| self.include.is_empty() || self.include.contains(&loc.name))
+            .filter(|loc| {
+                let excluded = self.exclude.contains(&loc.name);
+                if excluded {
+                    debug!("Default location excluded: {}", loc.name);
+                }
+                !excluded
+            })
+            .map(|mut loc| {
+                if let Some(overrides) = self.overrides.get(&loc.name) {
+                    debug!("Overriding default location: {}", loc.name);
+                    if !overrides.paths.is_empty() {
+                        loc.paths = overrides.paths.clone();
+                    }
+                    loc.excludes.extend(overrides.excludes.clone());
+                }
+                loc
+            })
+            .collect()
+    }
+
+    /// Merge another defaults selection on top of this one
+    pub fn merge(&mut self, other: DefaultsConfig) {
+        self.include.extend(other.include);
+        self.exclude.extend(other.exclude);
+        for (name, other) in other.overrides {
+            let overrides = self.overrides.entry(name).or_default();
+            if !other.paths.is_empty() {
+                overrides.paths = other.paths;
+            }
+            overrides.excludes.extend(other.excludes);
+        }
+    }
+}
+
+/// Package in a monorepo which is released independently
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Package {
+    /// Path of the package relative to the project root (can be left out
+    /// when overriding a package of an extended configuration)
+    #[serde(default)]
+    pub path: PathBuf,
+    /// Package configuration (loaded from `<path>/.release.yml` if not set).
+    /// Location paths are relative to the package path.
+    #[serde(flatten)]
+    pub config: Config,
+}
+
+/// Project root or package to run a workflow on
+#[derive(Debug, Clone)]
+pub struct Target {
+    /// Name of the package (`None` for the project root)
+    pub package: Option<String>,
+    /// Root directory of the project or package
+    pub root: PathBuf,
+    /// Resolved configuration
+    pub config: Config,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            extends: Vec::new(),
             name: None,
             repository: None,
+            host: None,
             version: None,
+            versions: BTreeMap::new(),
             default: Some(true),
+            defaults: DefaultsConfig::default(),
+            counter: None,
             ecosystem: None,
             ecosystems: Vec::new(),
             excludes: Vec::new(),
+            variables: BTreeMap::new(),
             locations: Vec::new(),
+            packages: BTreeMap::new(),
+            hooks: Hooks::default(),
         }
     }
 }
 
-impl From<&String> for BumpMode {
-    fn from(s: &String) -> Self {
-        match s.as_str() {
-            "patch" => BumpMode::Patch,
-            "minor" => BumpMode::Minor,
-            "major" => BumpMode::Major,
-            _ => BumpMode::Patch,
+/// Bump modes which can be selected by name
+static BUMP_MODES: [BumpMode; 3] = [BumpMode::Patch, BumpMode::Minor, BumpMode::Major];
+
+impl BumpMode {
+    /// Name of the bump mode (`None` for a specific version)
+    pub fn name(&self) -> Option<&'static str> {
+        match self {
+            BumpMode::Patch => Some("patch"),
+            BumpMode::Minor => Some("minor"),
+            BumpMode::Major => Some("major"),
+            BumpMode::Version(_) => None,
+        }
+    }
+}
+
+impl clap::ValueEnum for BumpMode {
+    fn value_variants<'a>() -> &'a [Self] {
+        &BUMP_MODES
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        let help = match self {
+            BumpMode::Patch => "Bump the patch version (x.y.Z+1)",
+            BumpMode::Minor => "Bump the minor version (x.Y+1.0)",
+            BumpMode::Major => "Bump the major version (X+1.0.0)",
+            BumpMode::Version(_) => return None,
+        };
+        self.name()
+            .map(|name| clap::builder::PossibleValue::new(name).help(help))
+    }
+}
+
+/// Closest name (by edit distance, ignoring case) if it is close enough to be
+/// a typo
+fn nearest_name<'a>(name: &str, names: &[&'a str]) -> Option<&'a str> {
+    let name = name.to_lowercase();
+    names
+        .iter()
+        .map(|candidate| (edit_distance(&name, &candidate.to_lowercase()), *candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.len() / 3).max(2))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Number of single character edits to turn one string into another
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != *cb);
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
         }
+        previous = current;
     }
+    previous[b.len()]
 }
 
 /// Location Pattern to match a file path and a regex pattern
@@ -84,35 +309,198 @@ pub struct LocationPattern {
     #[serde(default = "String::new")]
     pub name: String,
     /// Type of the location
-    #[serde(default, skip_serializing)]
-    #[allow(dead_code)]
+    #[serde(default, skip_serializing_if = "LocationType::is_version")]
     pub r#type: LocationType,
+    /// Format used for date based location types (strftime syntax)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+    /// Command of the plugin used by `plugin` locations
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin: Option<String>,
+    /// Timeout of the plugin in seconds (defaults to the hooks timeout)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+    /// Rhai script rendering the new value of every capture
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub script: Option<String>,
     /// If this is a default location
     #[serde(skip, default)]
     pub default: bool,
 
     #[serde(default = "Vec::new", skip_serializing)]
     pub ecosystems: Vec<String>,
+    /// Version stream the location belongs to (defaults to `version`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream: Option<String>,
 
     /// Paths to match
-    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
     pub paths: Vec<PathBuf>,
     /// Patterns to match
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    pub patterns: Vec<String>,
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<Pattern>,
     /// Excludes to ignore
     #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
     pub excludes: Vec<String>,
+    /// Fail if the location matches nothing
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub required: bool,
+    /// Expected number of matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expect: Option<ExpectedMatches>,
     /// Regexes to match (this is not serialized)
     #[serde(skip)]
     pub regexes: Vec<Regex>,
+    /// Compiled script of each regex (this is not serialized)
+    #[serde(skip)]
+    pub scripts: Vec<Option<Script>>,
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Expected number of matches of a location
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpectedMatches {
+    /// Minimum number of matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min: Option<usize>,
+    /// Maximum number of matches
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max: Option<usize>,
+}
+
+impl ExpectedMatches {
+    /// If a number of matches is expected
+    pub fn contains(&self, matches: usize) -> bool {
+        self.min.is_none_or(|min| matches >= min) && self.max.is_none_or(|max| matches <= max)
+    }
+}
+
+impl Display for ExpectedMatches {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) if min == max => write!(f, "exactly {min}"),
+            (Some(min), Some(max)) => write!(f, "between {min} and {max}"),
+            (Some(min), None) => write!(f, "at least {min}"),
+            (None, Some(max)) => write!(f, "at most {max}"),
+            (None, None) => write!(f, "any number"),
+        }
+    }
+}
+
+/// User defined variable which can be used as a placeholder in patterns
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Variable {
+    /// Literal value, escaped before being used in a pattern
+    Literal(String),
+    /// Regex fragment used as-is in a pattern
+    Regex {
+        /// Regex fragment
+        regex: String,
+    },
+}
+
+/// Pattern of a location
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Pattern {
+    /// Regex (with placeholders)
+    Regex(String),
+    /// Regex whose captures are rendered by its own script
+    Scripted {
+        /// Regex (with placeholders)
+        pattern: String,
+        /// Rhai script rendering the new value of every capture
+        script: String,
+    },
+}
+
+impl Pattern {
+    /// Regex of the pattern
+    pub fn as_str(&self) -> &str {
+        match self {
+            Pattern::Regex(pattern) | Pattern::Scripted { pattern, .. } => pattern,
+        }
+    }
+
+    /// Regex of the pattern, to update its placeholders
+    pub fn pattern_mut(&mut self) -> &mut String {
+        match self {
+            Pattern::Regex(pattern) | Pattern::Scripted { pattern, .. } => pattern,
+        }
+    }
+
+    /// Script of the pattern
+    pub fn script(&self) -> Option<&str> {
+        match self {
+            Pattern::Regex(_) => None,
+            Pattern::Scripted { script, .. } => Some(script),
+        }
+    }
+}
+
+impl From<String> for Pattern {
+    fn from(pattern: String) -> Self {
+        Pattern::Regex(pattern)
+    }
+}
+
+impl From<&str> for Pattern {
+    fn from(pattern: &str) -> Self {
+        Pattern::Regex(pattern.to_string())
+    }
+}
+
+impl PartialEq<&str> for Pattern {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+/// Type of value written into a location
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub enum LocationType {
+    /// Semantic version of the project
     #[default]
     #[serde(rename = "version")]
     Version,
+    /// Release date (default format: `2025-01-31`)
+    #[serde(rename = "date")]
+    Date,
+    /// Release year (default format: `2025`)
+    #[serde(rename = "year")]
+    Year,
+    /// Release timestamp (default format: `2025-01-31T12:00:00Z`)
+    #[serde(rename = "timestamp")]
+    Timestamp,
+    /// Build number from `counter`, or derived from the version using a
+    /// `format` such as `{major}*10000+{minor}*100+{patch}`
+    #[serde(rename = "counter")]
+    Counter,
+    /// Edits returned by an external `plugin` executable
+    #[serde(rename = "plugin")]
+    Plugin,
+}
+
+impl LocationType {
+    /// If the location type is a version
+    pub fn is_version(&self) -> bool {
+        matches!(self, LocationType::Version)
+    }
+
+    /// If the location is patched by a plugin instead of patterns
+    pub fn is_plugin(&self) -> bool {
+        matches!(self, LocationType::Plugin)
+    }
+
+    /// Default strftime format for date based location types
+    pub fn default_format(&self) -> Option<&'static str> {
+        match self {
+            LocationType::Version | LocationType::Counter | LocationType::Plugin => None,
+            LocationType::Date => Some("%Y-%m-%d"),
+            LocationType::Year => Some("%Y"),
+            LocationType::Timestamp => Some("%Y-%m-%dT%H:%M:%SZ"),
+        }
+    }
 }
 
 impl Config {
@@ -122,45 +510,169 @@ impl Config {
     }
 
     /// Load YAML the configuration from a file path
-    pub fn load(root: &PathBuf, path: &PathBuf) -> Result<Self> {
+    ///
+    /// `variables` are `key=value` overrides (from the CLI) which take priority
+    /// over `PRM_VAR_*` environment variables and the configuration file.
+    pub fn load(
+        root: &Path,
+        path: &Path,
+        variables: &[(String, String)],
+        defaults: &Defaults,
+    ) -> Result<Self> {
         let resroot = root.canonicalize()?;
         debug!("Project Root: {:?}", resroot);
 
         let respath = resroot.join(path);
         debug!("Loading configuration from: {:?}", respath);
 
-        let config_data = std::fs::read_to_string(respath)
+        let mut config = Self::read(&respath, &mut Vec::new())?;
+        config.resolve(&resroot, variables, defaults)?;
+
+        info!("Configuration loaded successfully");
+
+        Ok(config)
+    }
+
+    /// Read a configuration file and the configurations it `extends`.
+    ///
+    /// Bases are merged in order with the configuration itself applied last.
+    fn read(path: &Path, stack: &mut Vec<PathBuf>) -> Result<Self> {
+        let config_data = std::fs::read_to_string(path)
             .map_err(|e| anyhow::anyhow!("Failed to read configuration file: {:?}", e))?;
-        let mut config: Self = serde_yaml::from_str(&config_data)?;
+        let config: Self = serde_yaml::from_str(&config_data)?;
+        if config.extends.is_empty() {
+            return Ok(config);
+        }
+
+        let canonical = path.canonicalize()?;
+        if stack.contains(&canonical) {
+            return Err(anyhow::anyhow!(
+                "Configuration extends itself: {}",
+                canonical.display()
+            ));
+        }
+        stack.push(canonical);
 
-        if let Some(eco) = &config.ecosystem {
+        let mut merged = Config {
+            default: None,
+            ..Default::default()
+        };
+        for base in &config.extends {
+            let base_path = match base.strip_prefix(PRESET_PREFIX) {
+                Some(preset) => Self::preset_path(preset)?,
+                None => path.parent().unwrap_or(Path::new(".")).join(base),
+            };
+            debug!("Extending configuration: {:?}", base_path);
+            merged.merge(Self::read(&base_path, stack)?);
+        }
+        stack.pop();
+
+        merged.merge(config);
+        merged.extends = Vec::new();
+        Ok(merged)
+    }
+
+    /// Path of a preset in the presets directory (`PRM_PRESETS` or the user
+    /// configuration directory)
+    fn preset_path(name: &str) -> Result<PathBuf> {
+        let directory = std::env::var_os(PRESETS_ENV)
+            .map(PathBuf::from)
+            .or_else(|| user_config_dir().map(|dir| dir.join("presets")))
+            .ok_or_else(|| anyhow::anyhow!("No presets directory found for: {}", name))?;
+
+        ["yml", "yaml"]
+            .iter()
+            .map(|ext| directory.join(format!("{name}.{ext}")))
+            .find(|path| path.exists())
+            .ok_or_else(|| {
+                anyhow::anyhow!("Preset not found: {} (in {})", name, directory.display())
+            })
+    }
+
+    /// Merge another configuration on top of this one.
+    ///
+    /// Scalars are overridden, excludes and ecosystems are concatenated, and
+    /// locations, variables, versions and packages are merged by name. Hooks
+    /// are appended to the hooks of this configuration.
+    pub fn merge(&mut self, other: Config) {
+        self.name = other.name.or(self.name.take());
+        self.repository = other.repository.or(self.repository.take());
+        self.host = other.host.or(self.host.take());
+        self.version = other.version.or(self.version.take());
+        self.default = other.default.or(self.default.take());
+        self.defaults.merge(other.defaults);
+        self.counter = other.counter.or(self.counter.take());
+        self.ecosystem = other.ecosystem.or(self.ecosystem.take());
+
+        for ecosystem in other.ecosystems {
+            if !self.ecosystems.contains(&ecosystem) {
+                self.ecosystems.push(ecosystem);
+            }
+        }
+        self.excludes.extend(other.excludes);
+        self.variables.extend(other.variables);
+        self.versions.extend(other.versions);
+        for (name, package) in other.packages {
+            match self.packages.get_mut(&name) {
+                Some(existing) => {
+                    if !package.path.as_os_str().is_empty() {
+                        existing.path = package.path;
+                    }
+                    existing.config.merge(package.config);
+                }
+                None => {
+                    self.packages.insert(name, package);
+                }
+            }
+        }
+        self.hooks.merge(other.hooks);
+
+        for location in other.locations {
+            match self.locations.iter_mut().find(|l| l.name == location.name) {
+                Some(existing) => existing.merge(location),
+                None => self.locations.push(location),
+            }
+        }
+    }
+
+    /// Resolve defaults, variables and placeholders of a loaded configuration.
+    ///
+    /// Packages are resolved relative to their own path in the project `root`.
+    pub fn resolve(
+        &mut self,
+        root: &Path,
+        variables: &[(String, String)],
+        defaults: &Defaults,
+    ) -> Result<()> {
+        if let Some(eco) = &self.ecosystem {
             debug!("Using ecosystem: {}", eco);
-            config.ecosystems.push(eco.clone());
+            self.ecosystems.push(eco.clone());
         }
 
         // Defaults
-        if config.use_default() {
-            let defaults = Defaults::load()?;
+        if self.use_default() {
+            let mut defaults = defaults.clone();
+            defaults.locations = self.defaults.apply(defaults.locations);
             debug!(
                 "Using default locations ({} locations)",
-                config.locations.len()
+                self.locations.len()
             );
 
-            if config.ecosystems.is_empty() {
+            if self.ecosystems.is_empty() {
                 debug!("No ecosystems specified, using all locations");
-                config.locations.extend(defaults.locations);
+                self.locations.extend(defaults.locations);
             } else {
                 debug!("Filtering locations by ecosystems");
-                config.ecosystems.iter().for_each(|eco| {
+                self.ecosystems.iter().for_each(|eco| {
                     defaults.locations.iter().for_each(|loc| {
                         if loc.ecosystems.contains(eco)
                             || loc.ecosystems.contains(&"All".to_string())
                         {
-                            if config.locations.iter().any(|l| l.name == loc.name) {
+                            if self.locations.iter().any(|l| l.name == loc.name) {
                                 debug!("Location already exists, skipping: {}", loc.name);
                             } else {
                                 debug!("Adding location: {}", loc.name);
-                                config.locations.push(loc.clone());
+                                self.locations.push(loc.clone());
                             }
                         }
                     });
@@ -168,25 +680,163 @@ impl Config {
             }
         }
 
+        // Version streams are stored in the release file
+        if self.use_default() {
+            for stream in self.versions.keys() {
+                self.locations
+                    .push(LocationPattern::stream_release_file(stream));
+            }
+        }
+
         // Update excludes paths
-        if !config.excludes.is_empty() {
+        if !self.excludes.is_empty() {
             debug!("Adding global excludes to default locations");
-            for loc in config.locations.iter_mut() {
-                loc.excludes.extend(config.excludes.clone());
+            for loc in self.locations.iter_mut() {
+                loc.excludes.extend(self.excludes.clone());
+            }
+        }
+
+        // Plugins default to the timeout of the hooks
+        for loc in self.locations.iter_mut() {
+            if loc.r#type.is_plugin() {
+                loc.timeout = loc.timeout.or(self.hooks.timeout);
             }
         }
 
+        // Variable overrides
+        for (key, value) in std::env::vars() {
+            if let Some(name) = key.strip_prefix(VARIABLE_ENV_PREFIX) {
+                debug!("Variable from environment: {}", name);
+                self.set_variable(name, &value);
+            }
+        }
+        for (key, value) in variables {
+            debug!("Variable from arguments: {}", key);
+            self.set_variable(key, value);
+        }
+
         // Update any placeholders in the configuration
-        config.update_placeholders();
+        self.update_placeholders()?;
+        // The counter is stored with the default version, so it is only
+        // incremented by bumps of the default version
+        if let Some(loc) = self
+            .locations
+            .iter()
+            .find(|loc| loc.stream.is_some() && loc.r#type == LocationType::Counter)
+        {
+            return Err(Error::StreamCounter(loc.name.clone()).into());
+        }
+        // Invalid scripts are reported when the configuration is loaded
+        for loc in self.locations.iter_mut() {
+            loc.compile()?;
+        }
 
-        info!("Configuration loaded successfully");
+        // Packages
+        for (name, package) in self.packages.iter_mut() {
+            if package.path.as_os_str().is_empty() {
+                anyhow::bail!("Package `{}` has no `path`", name);
+            }
+            let package_root = root.join(&package.path);
+            let nested = package_root.join(".release.yml");
+            if package.config.version.is_none()
+                && package.config.locations.is_empty()
+                && nested.exists()
+            {
+                debug!("Loading package configuration from: {:?}", nested);
+                package.config = Self::read(&nested, &mut Vec::new())?;
+            } else if package.config.use_default() {
+                // Inline package versions are stored in the root release file
+                package
+                    .config
+                    .locations
+                    .push(LocationPattern::package_release_file(name, &package.path));
+            }
+            if package.config.name.is_none() {
+                package.config.name = Some(name.clone());
+            }
+            if package.config.repository.is_none() {
+                package.config.repository = self.repository.clone();
+            }
+            if package.config.host.is_none() {
+                package.config.host = self.host.clone();
+            }
+            package.config.resolve(&package_root, variables, defaults)?;
+        }
 
-        Ok(config)
+        // Files of a package are bumped with the package, not the project root
+        for package in self.packages.values() {
+            let components: Vec<String> = package
+                .path
+                .components()
+                .filter_map(|c| match c {
+                    std::path::Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                    _ => None,
+                })
+                .collect();
+            if components.is_empty() {
+                continue;
+            }
+            let exclude = format!("/{}/", components.join("/"));
+            for loc in self.locations.iter_mut() {
+                if !loc.excludes.contains(&exclude) {
+                    loc.excludes.push(exclude.clone());
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Override a variable. Names match the configured variables ignoring case
+    /// (new variables are lowercase), so `PRM_VAR_IMAGE` and `--var IMAGE=...`
+    /// both set `image`.
+    fn set_variable(&mut self, name: &str, value: &str) {
+        let key = self
+            .variables
+            .keys()
+            .find(|key| key.eq_ignore_ascii_case(name))
+            .cloned()
+            .unwrap_or_else(|| name.to_lowercase());
+        self.variables
+            .insert(key, Variable::Literal(value.to_string()));
     }
 
     // Update placeholders with semantic version regexes
     #[allow(unused_assignments)]
-    fn update_placeholders(&mut self) {
+    pub(crate) fn update_placeholders(&mut self) -> Result<()> {
+        // User defined variables are replaced first so they can use the
+        // built-in placeholders below
+        let mut variables = Vec::new();
+        for name in self.variables.keys() {
+            let pattern = self.variable_pattern(name, &mut Vec::new())?;
+            variables.push((format!("{{{name}}}"), pattern));
+        }
+        self.locations.iter_mut().for_each(|loc| {
+            loc.patterns.iter_mut().for_each(|pattern| {
+                let pattern = pattern.pattern_mut();
+                variables.iter().for_each(|(ph, repl)| {
+                    *pattern = pattern.replace(ph, repl);
+                });
+            });
+        });
+
+        // Date placeholders of date based locations follow their `format`
+        for loc in self.locations.iter_mut() {
+            if loc.r#type.default_format().is_none() {
+                continue;
+            }
+            let Some(format) = &loc.format else {
+                continue;
+            };
+            let pattern = format!("({})", date_format_pattern(&loc.name, format)?);
+            loc.patterns.iter_mut().for_each(|p| {
+                let p = p.pattern_mut();
+                for placeholder in ["{date}", "{year}", "{timestamp}"] {
+                    *p = p.replace(placeholder, &pattern);
+                }
+            });
+        }
+
         // TODO: Add pre-release and build metadata
         let semver = "([0-9]+\\.[0-9]+\\.[0-9]+)";
         let mut placeholders = vec![
@@ -195,19 +845,32 @@ impl Config {
             ("{patch}", semver),
             ("{version}", semver),
             ("{semver}", semver),
+            ("{date}", "([0-9]{4}-[0-9]{2}-[0-9]{2})"),
+            ("{year}", "([0-9]{4})"),
+            ("{counter}", "([0-9]+)"),
+            (
+                "{timestamp}",
+                "([0-9]{4}-[0-9]{2}-[0-9]{2}T[0-9]{2}:[0-9]{2}:[0-9]{2}(?:\\.[0-9]+)?(?:Z|[+-][0-9]{2}:?[0-9]{2})?)",
+            ),
         ];
 
         // TODO: we should probably do something else here
         let mut owner_case = "".to_string();
         let mut name_case = "".to_string();
         let mut repo_case = "".to_string();
+        let mut host_case = "".to_string();
+
+        if let Some(host) = &self.host {
+            host_case = format!("(?i){}(?-i)", regex::escape(host));
+            placeholders.push(("{host}", &host_case));
+        }
 
         if let Some(repo) = &self.repository {
-            // repo could be `owner/name` or `name`
+            // repo could be `owner/name`, `group/subgroup/name` or `name`
             // repo isn't case sensitive so we
             repo_case = format!("(?i){}(?-i)", repo);
 
-            if let Some((owner, name)) = repo.split_once('/') {
+            if let Some((owner, name)) = repo.rsplit_once('/') {
                 debug!("Full repository name: {}/{}", owner, name);
                 owner_case = format!("(?i){}(?-i)", owner);
                 name_case = format!("(?i){}(?-i)", name);
@@ -224,11 +887,188 @@ impl Config {
 
         self.locations.iter_mut().for_each(|loc| {
             loc.patterns.iter_mut().for_each(|pattern| {
+                let pattern = pattern.pattern_mut();
                 placeholders.iter().for_each(|(ph, repl)| {
                     *pattern = pattern.replace(ph, repl);
                 });
             });
         });
+        Ok(())
+    }
+
+    /// Resolve a variable into a regex fragment, including any variables it references
+    fn variable_pattern(&self, name: &str, stack: &mut Vec<String>) -> Result<String> {
+        if stack.iter().any(|n| n == name) {
+            stack.push(name.to_string());
+            return Err(anyhow::anyhow!(
+                "Variable references itself: {}",
+                stack.join(" -> ")
+            ));
+        }
+        let (text, literal) = match self.variables.get(name) {
+            Some(Variable::Literal(value)) => (value, true),
+            Some(Variable::Regex { regex }) => (regex, false),
+            None => return Err(anyhow::anyhow!("Unknown variable: {}", name)),
+        };
+        stack.push(name.to_string());
+
+        let mut pattern = String::new();
+        let mut last = 0;
+        for capture in PLACEHOLDER.captures_iter(text) {
+            let (reference, key) = (capture.get(0).unwrap(), &capture[1]);
+            let before = &text[last..reference.start()];
+            pattern.push_str(&if literal {
+                regex::escape(before)
+            } else {
+                before.to_string()
+            });
+
+            if self.variables.contains_key(key) {
+                pattern.push_str(&self.variable_pattern(key, stack)?);
+            } else {
+                // Built-in placeholders are replaced later
+                pattern.push_str(reference.as_str());
+            }
+            last = reference.end();
+        }
+        let rest = &text[last..];
+        pattern.push_str(&if literal {
+            regex::escape(rest)
+        } else {
+            rest.to_string()
+        });
+
+        stack.pop();
+        Ok(pattern)
+    }
+
+    /// Project root and packages to run on, or only a single package
+    pub fn targets(&self, root: &Path, package: Option<&str>) -> Result<Vec<Target>> {
+        if let Some(name) = package {
+            let package = self
+                .packages
+                .get(name)
+                .ok_or_else(|| anyhow::anyhow!("Unknown package: {}", name))?;
+            return Ok(vec![Target {
+                package: Some(name.to_string()),
+                root: root.join(&package.path),
+                config: package.config.clone(),
+            }]);
+        }
+
+        let mut targets = Vec::new();
+        // Monorepo roots without a version are only a container for packages
+        if self.packages.is_empty() || self.version.is_some() {
+            targets.push(Target {
+                package: None,
+                root: root.to_path_buf(),
+                config: self.clone(),
+            });
+        }
+        targets.extend(self.packages.iter().map(|(name, package)| Target {
+            package: Some(name.clone()),
+            root: root.join(&package.path),
+            config: package.config.clone(),
+        }));
+        Ok(targets)
+    }
+
+    /// Current version of a stream (`None` is the default `version` stream)
+    pub fn stream_version(&self, stream: Option<&str>) -> Result<Option<&String>> {
+        match stream {
+            None => Ok(self.version.as_ref()),
+            Some(name) => self
+                .versions
+                .get(name)
+                .map(Some)
+                .ok_or_else(|| anyhow::anyhow!("Unknown version stream: {}", name)),
+        }
+    }
+
+    /// All version streams, starting with the default `version` stream
+    pub fn streams(&self) -> Vec<(Option<String>, Option<String>)> {
+        let mut streams = vec![(None, self.version.clone())];
+        streams.extend(
+            self.versions
+                .iter()
+                .map(|(name, version)| (Some(name.clone()), Some(version.clone()))),
+        );
+        streams
+    }
+
+    /// Locations which belong to a version stream
+    pub fn stream_locations(&self, stream: Option<&str>) -> Vec<LocationPattern> {
+        self.locations
+            .iter()
+            .filter(|loc| loc.stream.as_deref() == stream)
+            .cloned()
+            .collect()
+    }
+
+    /// Add excludes for locations (by name) to a configuration file.
+    ///
+    /// Locations defined in the file get the exclude directly, default
+    /// locations get it as a `defaults.overrides` exclude. The file is edited
+    /// in place so its comments and formatting are kept.
+    pub fn persist_excludes(path: &Path, excludes: &[(String, PathBuf)]) -> Result<()> {
+        let config_data = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read configuration file: {:?}", e))?;
+        let mut config: Self = serde_yaml::from_str(&config_data)?;
+        let mut document = yaml::Document::new(&config_data);
+
+        for (name, exclude) in excludes {
+            let exclude = exclude.display().to_string();
+            let (target, scope) = match config.locations.iter_mut().find(|l| &l.name == name) {
+                Some(location) => (
+                    &mut location.excludes,
+                    document
+                        .item(document.root(), "locations", "name", name)
+                        .ok_or_else(|| anyhow::anyhow!("Location not found in file: {}", name))?,
+                ),
+                None => {
+                    let defaults = document.mapping(document.root(), "defaults")?;
+                    let overrides = document.mapping(defaults, "overrides")?;
+                    (
+                        &mut config
+                            .defaults
+                            .overrides
+                            .entry(name.clone())
+                            .or_default()
+                            .excludes,
+                        document.mapping(overrides, name)?,
+                    )
+                }
+            };
+            if !target.contains(&exclude) {
+                document.append(scope, "excludes", &[yaml::scalar(&exclude)?])?;
+                target.push(exclude);
+            }
+        }
+
+        std::fs::write(path, document.to_string())?;
+        Ok(())
+    }
+
+    /// Add locations to a configuration file, skipping names already defined.
+    /// The file is edited in place so its comments and formatting are kept.
+    pub fn persist_locations(path: &Path, locations: &[LocationPattern]) -> Result<()> {
+        let config_data = std::fs::read_to_string(path)
+            .map_err(|e| anyhow::anyhow!("Failed to read configuration file: {:?}", e))?;
+        let config: Self = serde_yaml::from_str(&config_data)?;
+        let mut document = yaml::Document::new(&config_data);
+
+        let mut items = Vec::new();
+        for location in locations {
+            if !config.locations.iter().any(|l| l.name == location.name) {
+                items.push(serde_yaml::to_string(location)?);
+            }
+        }
+        if !items.is_empty() {
+            document.append(document.root(), "locations", &items)?;
+        }
+
+        std::fs::write(path, document.to_string())?;
+        Ok(())
     }
 
     /// Write the configuration to a file path
@@ -241,20 +1081,199 @@ impl Config {
 }
 
 impl LocationPattern {
+    /// Expected number of matches (`required` expects at least one match)
+    pub fn expected_matches(&self) -> Option<ExpectedMatches> {
+        let mut expected = self.expect.unwrap_or_default();
+        if self.required && expected.min.is_none_or(|min| min == 0) {
+            expected.min = Some(1);
+        }
+        (expected != ExpectedMatches::default()).then_some(expected)
+    }
+
+    /// Merge another location (with the same name) on top of this one
+    pub fn merge(&mut self, other: LocationPattern) {
+        if !other.r#type.is_version() {
+            self.r#type = other.r#type;
+        }
+        self.format = other.format.or(self.format.take());
+        self.plugin = other.plugin.or(self.plugin.take());
+        self.timeout = other.timeout.or(self.timeout);
+        self.script = other.script.or(self.script.take());
+        self.stream = other.stream.or(self.stream.take());
+        if !other.ecosystems.is_empty() {
+            self.ecosystems = other.ecosystems;
+        }
+        if !other.paths.is_empty() {
+            self.paths = other.paths;
+        }
+        if !other.patterns.is_empty() {
+            self.patterns = other.patterns;
+        }
+        self.excludes.extend(other.excludes);
+        self.required |= other.required;
+        self.expect = other.expect.or(self.expect);
+    }
+
+    /// Default location for an inline package version in the root release file
+    pub fn package_release_file(package: &str, path: &Path) -> Self {
+        // Location paths are relative to the package
+        let root: PathBuf = path
+            .components()
+            .filter(|c| matches!(c, std::path::Component::Normal(_)))
+            .map(|_| "..")
+            .collect();
+        Self {
+            name: format!("Release File ({package})"),
+            default: true,
+            paths: vec![root.join(".release.yml"), root.join(".release.yaml")],
+            // One pattern per indentation of the package, the version has to be
+            // in the block of the package (indented further than its name)
+            patterns: (1..=8)
+                .map(|indent| {
+                    format!(
+                        "(?m)^packages:[^\\n]*\\n(?:[ \\t]+[^\\n]*\\n|[ \\t]*\\n)*?{indent}{}:[^\\n]*\\n(?:{indent} +[^\\n]*\\n|[ \\t]*\\n)*?{indent} +version:[ \\t]*[\"|\']?{{version}}[\"|\']?",
+                        regex::escape(package),
+                        indent = " ".repeat(indent)
+                    )
+                    .into()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Default location for a version stream in the release file
+    pub fn stream_release_file(stream: &str) -> Self {
+        Self {
+            name: format!("Release File ({stream})"),
+            default: true,
+            stream: Some(stream.to_string()),
+            paths: vec![
+                PathBuf::from(".release.yml"),
+                PathBuf::from(".release.yaml"),
+            ],
+            patterns: vec![format!(
+                "(?m)^versions:[^\\n]*\\n(?:[ \\t]+[^\\n]*\\n)*?[ \\t]+{}:[ \\t]*[\"|\']?{{version}}[\"|\']?",
+                regex::escape(stream)
+            )
+            .into()],
+            ..Default::default()
+        }
+    }
+
     /// Create a new LocationPattern
-    pub fn regexes(patterns: &[String]) -> Result<Vec<regex::Regex>> {
-        Ok(patterns
-            .iter()
-            .map(|pattern_str| match regex::Regex::new(pattern_str) {
-                Ok(pattern) => Ok(pattern),
-                Err(e) => {
-                    debug!("Error: {:?}", e);
-                    Err(e)
-                }
-            })
-            .filter_map(Result::ok)
-            .collect::<Vec<regex::Regex>>())
+    ///
+    /// Every pattern needs at least one capture group, either the first unnamed
+    /// group or a named group such as `(?P<version>...)` or `(?P<major>...)`.
+    pub fn regexes(patterns: &[Pattern]) -> Result<Vec<regex::Regex>> {
+        let mut regexes = Vec::new();
+        for pattern in patterns {
+            if let Some(regex) = Self::regex(pattern)? {
+                regexes.push(regex);
+            }
+        }
+        Ok(regexes)
     }
+
+    /// Compile the regexes of the location and the script of each regex (its
+    /// pattern's script or the script of the location)
+    pub fn compile(&mut self) -> Result<()> {
+        let compile = |script: &str| {
+            Script::compile(script).map_err(|e| anyhow::anyhow!("Location `{}`: {}", self.name, e))
+        };
+        let script = self.script.as_deref().map(compile).transpose()?;
+
+        let (mut regexes, mut scripts) = (Vec::new(), Vec::new());
+        for pattern in &self.patterns {
+            let Some(regex) = Self::regex(pattern)? else {
+                continue;
+            };
+            regexes.push(regex);
+            scripts.push(match pattern.script() {
+                Some(own) => Some(compile(own)?),
+                None => script.clone(),
+            });
+        }
+        self.regexes = regexes;
+        self.scripts = scripts;
+        Ok(())
+    }
+
+    /// Regex of a pattern, invalid regexes are skipped
+    fn regex(pattern: &Pattern) -> Result<Option<regex::Regex>> {
+        let regex = match regex::Regex::new(pattern.as_str()) {
+            Ok(regex) => regex,
+            Err(e) => {
+                debug!("Error: {:?}", e);
+                return Ok(None);
+            }
+        };
+        if regex.captures_len() < 2 {
+            return Err(Error::MissingCaptureGroup(pattern.as_str().to_string()).into());
+        }
+        Ok(Some(regex))
+    }
+}
+
+/// If a path matches any of the exclude patterns.
+///
+/// Excludes are matched against the path relative to the `root` (starting
+/// with `/`), so `/crates/` excludes `<root>/crates/` but not a package which
+/// is itself located in a `crates` directory.
+pub(crate) fn is_excluded(excludes: &[String], root: &Path, path: &Path) -> bool {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let relative = format!("/{}", relative.display().to_string().replace('\\', "/"));
+    excludes
+        .iter()
+        .any(|pattern| relative.contains(pattern.as_str()))
+}
+
+/// Regex fragment matching dates written using a strftime `format`
+pub(crate) fn date_format_pattern(location: &str, format: &str) -> Result<String> {
+    use chrono::format::{Fixed, Item, Numeric, StrftimeItems};
+
+    let mut pattern = String::new();
+    for item in StrftimeItems::new(format) {
+        match item {
+            Item::Literal(text) | Item::Space(text) => pattern.push_str(&regex::escape(text)),
+            Item::OwnedLiteral(text) | Item::OwnedSpace(text) => {
+                pattern.push_str(&regex::escape(&text))
+            }
+            Item::Numeric(Numeric::Year, _) => pattern.push_str("[0-9]{4}"),
+            Item::Numeric(Numeric::Timestamp, _) => pattern.push_str("[0-9]+"),
+            Item::Numeric(..) => pattern.push_str("[0-9]{1,3}"),
+            Item::Fixed(Fixed::TimezoneOffset)
+            | Item::Fixed(Fixed::TimezoneOffsetColon)
+            | Item::Fixed(Fixed::TimezoneOffsetColonZ)
+            | Item::Fixed(Fixed::TimezoneOffsetZ) => {
+                pattern.push_str("(?:Z|[+-][0-9]{2}:?[0-9]{2})")
+            }
+            Item::Fixed(Fixed::Nanosecond)
+            | Item::Fixed(Fixed::Nanosecond3)
+            | Item::Fixed(Fixed::Nanosecond6)
+            | Item::Fixed(Fixed::Nanosecond9) => pattern.push_str("(?:\\.[0-9]+)?"),
+            Item::Fixed(Fixed::RFC3339) => pattern.push_str(
+                "[0-9]{4}-[0-9]{2}-[0-9]{2}T[0-9]{2}:[0-9]{2}:[0-9]{2}(?:\\.[0-9]+)?(?:Z|[+-][0-9]{2}:?[0-9]{2})",
+            ),
+            Item::Fixed(_) => pattern.push_str("[A-Za-z0-9,:+ -]+?"),
+            Item::Error => {
+                return Err(
+                    Error::InvalidDateFormat(location.to_string(), format.to_string()).into(),
+                );
+            }
+        }
+    }
+    Ok(pattern)
+}
+
+/// User configuration directory (`$XDG_CONFIG_HOME/patch-release-me` or
+/// `~/.config/patch-release-me`)
+pub fn user_config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("patch-release-me"))
 }
 
 impl Display for LocationPattern {
@@ -271,6 +1290,28 @@ impl Display for LocationPattern {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_expected_matches() {
+        let location: LocationPattern =
+            serde_yaml::from_str("name: Docker\nrequired: true\nexpect:\n  max: 3\n").unwrap();
+        let expected = location.expected_matches().unwrap();
+        assert_eq!(expected.to_string(), "between 1 and 3");
+        assert!(!expected.contains(0));
+        assert!(expected.contains(3));
+        assert!(!expected.contains(4));
+
+        let location = LocationPattern::default();
+        assert_eq!(location.expected_matches(), None);
+    }
+
+    #[test]
+    fn test_nearest_name() {
+        let names = ["Rust Cargo", "Documentation", "Release File"];
+        assert_eq!(nearest_name("Rust Crago", &names), Some("Rust Cargo"));
+        assert_eq!(nearest_name("documentaton", &names), Some("Documentation"));
+        assert_eq!(nearest_name("Python", &names), None);
+    }
+
     #[test]
     fn test_placeholder() {
         let mut config = Config {
@@ -279,17 +1320,17 @@ mod tests {
                 name: "Cargo.toml".to_string(),
                 paths: vec![PathBuf::from("Cargo.toml")],
                 patterns: vec![
-                    "version = \"{version}\"".to_string(),
-                    "semver = \"{semver}\"".to_string(),
-                    "major = \"{major}\"".to_string(),
-                    "minor = \"{minor}\"".to_string(),
-                    "patch = \"{patch}\"".to_string(),
+                    "version = \"{version}\"".into(),
+                    "semver = \"{semver}\"".into(),
+                    "major = \"{major}\"".into(),
+                    "minor = \"{minor}\"".into(),
+                    "patch = \"{patch}\"".into(),
                 ],
                 ..Default::default()
             }],
             ..Default::default()
         };
-        config.update_placeholders();
+        config.update_placeholders().unwrap();
 
         let loc = &config.locations[0];
         assert_eq!(loc.patterns[0], "version = \"([0-9]+\\.[0-9]+\\.[0-9]+)\"");
@@ -308,15 +1349,15 @@ mod tests {
                 name: "Cargo.toml".to_string(),
                 paths: vec![PathBuf::from("Cargo.toml")],
                 patterns: vec![
-                    "repository = \"{repository}\"".to_string(),
-                    "owner = \"{owner}\"".to_string(),
-                    "name = \"{name}\"".to_string(),
+                    "repository = \"{repository}\"".into(),
+                    "owner = \"{owner}\"".into(),
+                    "name = \"{name}\"".into(),
                 ],
                 ..Default::default()
             }],
             ..Default::default()
         };
-        config.update_placeholders();
+        config.update_placeholders().unwrap();
 
         let loc = &config.locations[0];
         // All repo related placeholders should be replaced with the repo + case sensitive regex
@@ -327,4 +1368,482 @@ mod tests {
         assert_eq!(loc.patterns[1], "owner = \"(?i)42ByteLabs(?-i)\"");
         assert_eq!(loc.patterns[2], "name = \"(?i)patch-release-me(?-i)\"");
     }
+
+    #[test]
+    fn test_regexes_missing_capture_group() {
+        let result = LocationPattern::regexes(&["version = 1.2.3".into()]);
+        assert!(result.is_err());
+
+        let result = LocationPattern::regexes(&["MAJOR=(?P<major>[0-9]+)".into()]);
+        assert_eq!(result.unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_pattern_scripts() {
+        let mut location: LocationPattern = serde_yaml::from_str(
+            r#"
+name: Docs
+paths: ["README.md"]
+script: version.to_string()
+patterns:
+  - "version ([0-9.]+)"
+  - pattern: "docs for ([0-9]+\\.x)"
+    script: '`${version.major}.x`'
+"#,
+        )
+        .unwrap();
+        assert_eq!(location.patterns[1].script(), Some("`${version.major}.x`"));
+
+        location.compile().unwrap();
+        let version = semver::Version::new(2, 1, 0);
+        let rendered: Vec<String> = location
+            .scripts
+            .iter()
+            .map(|script| script.as_ref().unwrap().render("", &version).unwrap())
+            .collect();
+        assert_eq!(rendered, vec!["2.1.0", "2.x"]);
+
+        location.patterns[1] = Pattern::Scripted {
+            pattern: "docs for ([0-9]+\\.x)".to_string(),
+            script: "version.".to_string(),
+        };
+        let error = location.compile().unwrap_err().to_string();
+        assert!(error.starts_with("Location `Docs`: "), "{error}");
+    }
+
+    #[test]
+    fn test_placeholder_variables() {
+        let mut config = Config {
+            repository: Some("acme/api".to_string()),
+            variables: BTreeMap::from([
+                (
+                    "registry".to_string(),
+                    Variable::Literal("ghcr.io".to_string()),
+                ),
+                (
+                    "image".to_string(),
+                    Variable::Literal("{registry}/{repository}".to_string()),
+                ),
+                (
+                    "suffix".to_string(),
+                    Variable::Regex {
+                        regex: "(?:-[a-z]+)?".to_string(),
+                    },
+                ),
+            ]),
+            locations: vec![LocationPattern {
+                name: "Docker".to_string(),
+                patterns: vec!["{image}:{version}{suffix}".into()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        config.update_placeholders().unwrap();
+
+        assert_eq!(
+            config.locations[0].patterns[0],
+            "ghcr\\.io/(?i)acme/api(?-i):([0-9]+\\.[0-9]+\\.[0-9]+)(?:-[a-z]+)?"
+        );
+    }
+
+    #[test]
+    fn test_set_variable() {
+        let mut config = Config {
+            variables: BTreeMap::from([(
+                "Image".to_string(),
+                Variable::Literal("ghcr.io/acme/api".to_string()),
+            )]),
+            ..Default::default()
+        };
+        config.set_variable("IMAGE", "docker.io/acme/api");
+        config.set_variable("Registry", "docker.io");
+
+        assert_eq!(
+            config.variables,
+            BTreeMap::from([
+                (
+                    "Image".to_string(),
+                    Variable::Literal("docker.io/acme/api".to_string())
+                ),
+                (
+                    "registry".to_string(),
+                    Variable::Literal("docker.io".to_string())
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_placeholder_variables_cycle() {
+        let mut config = Config {
+            variables: BTreeMap::from([
+                ("a".to_string(), Variable::Literal("{b}".to_string())),
+                ("b".to_string(), Variable::Literal("{a}".to_string())),
+            ]),
+            ..Default::default()
+        };
+        assert!(config.update_placeholders().is_err());
+    }
+
+    #[test]
+    fn test_placeholder_date_format() {
+        let mut config = Config {
+            locations: vec![LocationPattern {
+                name: "Changelog".to_string(),
+                r#type: LocationType::Date,
+                format: Some("%d/%m/%Y".to_string()),
+                patterns: vec!["Released: {date}".into()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        config.update_placeholders().unwrap();
+        let regexes = LocationPattern::regexes(&config.locations[0].patterns).unwrap();
+        let captures = regexes[0].captures("Released: 04/03/2025").unwrap();
+        assert_eq!(&captures[1], "04/03/2025");
+
+        config.locations[0].format = Some("%Y-%Q".to_string());
+        assert!(config.update_placeholders().is_err());
+    }
+
+    /// Values captured by the patterns of a location in some content
+    fn captured(location: LocationPattern, content: &str) -> Vec<String> {
+        let mut config = Config {
+            locations: vec![location],
+            ..Default::default()
+        };
+        config.update_placeholders().unwrap();
+        LocationPattern::regexes(&config.locations[0].patterns)
+            .unwrap()
+            .iter()
+            .flat_map(|regex| regex.captures_iter(content))
+            .map(|captures| captures[1].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_release_file_with_packages() {
+        let defaults: crate::defaults::Defaults =
+            serde_yaml::from_str(crate::defaults::DEFAULTS).unwrap();
+        let release_file = defaults
+            .locations
+            .into_iter()
+            .find(|l| l.name == "Release File")
+            .unwrap();
+        let content = "name: monorepo\nversion: 2.1.0\npackages:\n  api:\n    path: crates/api\n    version: 1.1.0\n";
+        assert_eq!(captured(release_file, content), vec!["2.1.0"]);
+    }
+
+    #[test]
+    fn test_package_release_file() {
+        let content = "version: 2.1.0\npackages:\n  api:\n    path: crates/api\n\n  web:\n    path: web\n    version: 1.4.0\n";
+        let api = LocationPattern::package_release_file("api", Path::new("crates/api"));
+        assert!(captured(api, content).is_empty());
+
+        let web = LocationPattern::package_release_file("web", Path::new("web"));
+        assert_eq!(captured(web, content), vec!["1.4.0"]);
+    }
+
+    #[test]
+    fn test_is_excluded() {
+        let excludes = vec!["/crates/".to_string(), "/target/".to_string()];
+        let root = Path::new("/project");
+        assert!(is_excluded(
+            &excludes,
+            root,
+            Path::new("/project/crates/api/Cargo.toml")
+        ));
+        assert!(!is_excluded(
+            &excludes,
+            root,
+            Path::new("/project/Cargo.toml")
+        ));
+
+        // Packages in a `crates` directory are not excluded from themselves
+        let package = Path::new("/project/crates/api");
+        assert!(!is_excluded(
+            &excludes,
+            package,
+            Path::new("/project/crates/api/Cargo.toml")
+        ));
+        assert!(is_excluded(
+            &excludes,
+            package,
+            Path::new("/project/crates/api/target/Cargo.toml")
+        ));
+    }
+
+    #[test]
+    fn test_stream_release_file() {
+        let mut config = Config {
+            version: Some("1.4.2".to_string()),
+            versions: BTreeMap::from([
+                ("api".to_string(), "3.0.0".to_string()),
+                ("schema".to_string(), "2.1.0".to_string()),
+            ]),
+            locations: vec![LocationPattern::stream_release_file("schema")],
+            ..Default::default()
+        };
+        config.update_placeholders().unwrap();
+        let regexes = LocationPattern::regexes(&config.locations[0].patterns).unwrap();
+
+        let content = "version: 1.4.2\nversions:\n  api: 3.0.0\n  schema: \"2.1.0\"\n";
+        let capture = regexes[0].captures(content).unwrap();
+        assert_eq!(&capture[1], "2.1.0");
+
+        assert_eq!(config.stream_locations(Some("schema")).len(), 1);
+        assert!(config.stream_locations(None).is_empty());
+        assert!(config.stream_version(Some("web")).is_err());
+    }
+
+    #[test]
+    fn test_targets() {
+        let config: Config = serde_yaml::from_str(
+            "packages:\n  api:\n    path: crates/api\n    version: 1.2.0\n  web:\n    path: web\n",
+        )
+        .unwrap();
+        let root = PathBuf::from("/project");
+
+        // Roots without a version only contain packages
+        let targets = config.targets(&root, None).unwrap();
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].package.as_deref(), Some("api"));
+        assert_eq!(targets[0].root, PathBuf::from("/project/crates/api"));
+        assert_eq!(targets[0].config.version.as_deref(), Some("1.2.0"));
+
+        let targets = config.targets(&root, Some("web")).unwrap();
+        assert_eq!(targets.len(), 1);
+        assert!(config.targets(&root, Some("cli")).is_err());
+
+        let location = LocationPattern::package_release_file("api", Path::new("crates/api"));
+        assert_eq!(location.paths[0], PathBuf::from("../../.release.yml"));
+    }
+
+    #[test]
+    fn test_root_excludes_packages() {
+        let mut config: Config = serde_yaml::from_str(
+            "version: 1.0.0\nexcludes: [/target/]\npackages:\n  web:\n    path: ./apps/web\n    version: 2.0.0\n",
+        )
+        .unwrap();
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        config
+            .resolve(root, &[], &Defaults::load_from(&[]).unwrap())
+            .unwrap();
+
+        let cargo = config
+            .locations
+            .iter()
+            .find(|loc| loc.name == "Rust Cargo")
+            .unwrap();
+        assert!(cargo.excludes.contains(&"/apps/web/".to_string()));
+        assert!(is_excluded(
+            &cargo.excludes,
+            root,
+            &root.join("apps/web/Cargo.toml")
+        ));
+        assert!(!is_excluded(
+            &cargo.excludes,
+            root,
+            &root.join("Cargo.toml")
+        ));
+        // The package itself is not excluded from its own locations
+        let web = &config.packages["web"].config;
+        assert!(
+            web.locations
+                .iter()
+                .all(|loc| !loc.excludes.contains(&"/apps/web/".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_plugin_timeout() {
+        let mut config: Config = serde_yaml::from_str(
+            "default: false\nhooks:\n  timeout: 10\nlocations:\n  - name: Descriptor\n    type: plugin\n    plugin: ./patch.sh\n    paths: ['*.desc']\n  - name: Schema\n    type: plugin\n    plugin: ./patch.sh\n    timeout: 2\n    paths: ['*.json']\n",
+        )
+        .unwrap();
+        config
+            .resolve(Path::new("."), &[], &Defaults::load_from(&[]).unwrap())
+            .unwrap();
+        assert_eq!(config.locations[0].timeout, Some(10));
+        assert_eq!(config.locations[1].timeout, Some(2));
+    }
+
+    #[test]
+    fn test_stream_counter() {
+        let mut config: Config = serde_yaml::from_str(
+            "default: false\ncounter: 7\nversions:\n  api: 3.0.0\nlocations:\n  - name: Build\n    type: counter\n    stream: api\n    paths: [build.gradle]\n    patterns: ['versionCode {counter}']\n",
+        )
+        .unwrap();
+        let error = config
+            .resolve(Path::new("."), &[], &Defaults::load_from(&[]).unwrap())
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("`Build` is a counter"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_extends() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = directory.path();
+        std::fs::write(
+            directory.join("base.yml"),
+            "name: base\ndefault: false\nexcludes: [/target/]\nlocations:\n  - name: Cargo\n    paths: [Cargo.toml]\n    patterns: ['version = \"{version}\"']\n    excludes: [/examples/]\n  - name: Docker\n    paths: [Dockerfile]\n    patterns: ['image:{version}']\n",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join(".release.yml"),
+            "extends: [base.yml]\nversion: 1.2.3\nexcludes: [/vendor/]\nlocations:\n  - name: Cargo\n    excludes: [/crates/]\n",
+        )
+        .unwrap();
+
+        let config = Config::read(&directory.join(".release.yml"), &mut Vec::new()).unwrap();
+
+        assert_eq!(config.name.as_deref(), Some("base"));
+        assert_eq!(config.version.as_deref(), Some("1.2.3"));
+        assert_eq!(config.default, Some(false));
+        assert_eq!(config.excludes, vec!["/target/", "/vendor/"]);
+        assert_eq!(config.locations.len(), 2);
+        assert_eq!(config.locations[0].paths, vec![PathBuf::from("Cargo.toml")]);
+        assert_eq!(config.locations[0].excludes, vec!["/examples/", "/crates/"]);
+    }
+
+    #[test]
+    fn test_extends_packages() {
+        let directory = tempfile::tempdir().unwrap();
+        let directory = directory.path();
+        std::fs::write(
+            directory.join("base.yml"),
+            "packages:\n  api:\n    path: crates/api\n    version: 1.2.0\n    locations:\n      - name: Cargo\n        paths: [Cargo.toml]\n        patterns: ['version = \"{version}\"']\n",
+        )
+        .unwrap();
+        std::fs::write(
+            directory.join(".release.yml"),
+            "extends: [base.yml]\npackages:\n  api:\n    excludes: [/fixtures/]\n  web:\n    path: web\n",
+        )
+        .unwrap();
+
+        let config = Config::read(&directory.join(".release.yml"), &mut Vec::new()).unwrap();
+        let api = &config.packages["api"];
+        assert_eq!(api.path, PathBuf::from("crates/api"));
+        assert_eq!(api.config.version.as_deref(), Some("1.2.0"));
+        assert_eq!(api.config.locations.len(), 1);
+        assert_eq!(api.config.excludes, vec!["/fixtures/"]);
+        assert_eq!(config.packages["web"].path, PathBuf::from("web"));
+    }
+
+    #[test]
+    fn test_defaults_selection() {
+        let selection: DefaultsConfig = serde_yaml::from_str(
+            "exclude: [Documentation]\noverrides:\n  Rust Cargo:\n    paths: [Cargo.toml]\n    excludes: [/fixtures/]\n",
+        )
+        .unwrap();
+        let locations = selection.apply(Defaults::load_from(&[]).unwrap().locations);
+
+        assert!(locations.iter().all(|l| l.name != "Documentation"));
+        let cargo = locations.iter().find(|l| l.name == "Rust Cargo").unwrap();
+        assert_eq!(cargo.paths, vec![PathBuf::from("Cargo.toml")]);
+        assert!(cargo.excludes.contains(&"/fixtures/".to_string()));
+        assert!(cargo.excludes.contains(&"/target/".to_string()));
+
+        let selection = DefaultsConfig {
+            include: vec!["Release File".to_string()],
+            ..Default::default()
+        };
+        let locations = selection.apply(Defaults::load_from(&[]).unwrap().locations);
+        assert_eq!(locations.len(), 1);
+    }
+
+    #[test]
+    fn test_placeholder_nested_repo() {
+        let mut config = Config {
+            repository: Some("group/sub/proj".to_string()),
+            host: Some("gitlab.example.com".to_string()),
+            locations: vec![LocationPattern {
+                name: "Registry".to_string(),
+                patterns: vec!["{host}/{owner}/{name}:{version}".into()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        config.update_placeholders().unwrap();
+
+        assert_eq!(
+            config.locations[0].patterns[0],
+            "(?i)gitlab\\.example\\.com(?-i)/(?i)group/sub(?-i)/(?i)proj(?-i):([0-9]+\\.[0-9]+\\.[0-9]+)"
+        );
+    }
+
+    #[test]
+    fn test_persist_locations() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path();
+        std::fs::write(
+            path,
+            "# Release configuration\nversion: 1.0.0\nlocations:\n  # Documentation\n  - name: Docs\n    paths: ['**/*.md']\n    patterns: ['v{version}']\n",
+        )
+        .unwrap();
+
+        let location = LocationPattern {
+            name: "Helm Chart".to_string(),
+            paths: vec![PathBuf::from("chart/Chart.yaml")],
+            patterns: vec!["appVersion: {version}".into()],
+            ..Default::default()
+        };
+        Config::persist_locations(
+            path,
+            &[
+                LocationPattern {
+                    name: "Docs".to_string(),
+                    ..Default::default()
+                },
+                location.clone(),
+            ],
+        )
+        .unwrap();
+        let content = std::fs::read_to_string(path).unwrap();
+        let config: Config = serde_yaml::from_str(&content).unwrap();
+
+        assert!(content.contains("  # Documentation\n  - name: Docs\n"));
+        assert_eq!(config.locations.len(), 2);
+        assert_eq!(config.locations[0].paths, vec![PathBuf::from("**/*.md")]);
+        assert_eq!(config.locations[1].name, location.name);
+        assert_eq!(config.locations[1].patterns, location.patterns);
+    }
+
+    #[test]
+    fn test_persist_excludes() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path();
+        std::fs::write(
+            path,
+            "# Release configuration\nversion: 1.0.0\necosystems:\n  - Rust\nlocations:\n  - name: Docs # README and guides\n    paths: ['**/*.md']\n    patterns: ['v{version}']\n",
+        )
+        .unwrap();
+
+        Config::persist_excludes(
+            path,
+            &[
+                ("Docs".to_string(), PathBuf::from("docs/example.md")),
+                (
+                    "Rust Cargo".to_string(),
+                    PathBuf::from("fixtures/Cargo.toml"),
+                ),
+            ],
+        )
+        .unwrap();
+        let content = std::fs::read_to_string(path).unwrap();
+        let config: Config = serde_yaml::from_str(&content).unwrap();
+
+        assert!(content.starts_with("# Release configuration\n"));
+        assert!(content.contains("  - name: Docs # README and guides\n"));
+        assert_eq!(config.ecosystems, vec!["Rust"]);
+        assert_eq!(config.locations[0].excludes, vec!["docs/example.md"]);
+        assert_eq!(
+            config.defaults.overrides["Rust Cargo"].excludes,
+            vec!["fixtures/Cargo.toml"]
+        );
+    }
 }
@@ -1,23 +1,90 @@
 //! This module loads the default locations. This is done at compile time
 //! by loading the `default.yml` file from the `src` directory of this crate.
+//!
+//! The compiled-in defaults can be extended at runtime by files in the user
+//! defaults directory (`~/.config/patch-release-me/defaults/*.yml`) and by
+//! files passed using `--defaults`, applied in that order.
 use anyhow::Result;
-use std::collections::HashMap;
+use log::debug;
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
 
 use crate::LocationPattern;
+use crate::config::{Config, is_excluded, user_config_dir};
 
 /// List of default Languages and Ecosystems supported
 pub const DEFAULTS: &str = include_str!("defaults.yml");
 
+/// Regex fragment used for repository placeholders while detecting ecosystems
+const ANY_REPOSITORY: &str = "[A-Za-z0-9_.-]+(?:/[A-Za-z0-9_.-]+)*";
+
 #[derive(Debug, Clone, serde::Deserialize)]
 pub struct Defaults {
-    #[serde(rename = "ecosystems")]
+    #[serde(rename = "ecosystems", default)]
     pub ecosystems: HashMap<String, Vec<String>>,
+    #[serde(default)]
     pub locations: Vec<LocationPattern>,
+    /// Names of default locations to remove
+    #[serde(default)]
+    pub disable: Vec<String>,
 }
 
 impl Defaults {
-    pub fn load() -> Result<Self> {
-        Ok(serde_yaml::from_str::<Self>(&DEFAULTS)?)
+    /// Load the compiled-in defaults merged with the user defaults and the
+    /// defaults `files` passed on the command line (`--defaults`)
+    pub fn load(files: &[PathBuf]) -> Result<Self> {
+        let mut layers = Self::user_layers()?;
+        layers.extend_from_slice(files);
+        Self::load_from(&layers)
+    }
+
+    /// Load the compiled-in defaults merged with the defaults `files` only
+    pub fn load_from(files: &[PathBuf]) -> Result<Self> {
+        let mut defaults = serde_yaml::from_str::<Self>(DEFAULTS)?;
+
+        for path in files {
+            debug!("Loading defaults from: {:?}", path);
+            let data = std::fs::read_to_string(path)
+                .map_err(|e| anyhow::anyhow!("Failed to read defaults file {:?}: {}", path, e))?;
+            defaults.merge(serde_yaml::from_str::<Self>(&data)?);
+        }
+
+        Ok(defaults)
+    }
+
+    /// Defaults files from the user defaults directory
+    fn user_layers() -> Result<Vec<PathBuf>> {
+        let mut layers = Vec::new();
+        if let Some(directory) = user_config_dir().map(|dir| dir.join("defaults")) {
+            if directory.is_dir() {
+                let mut files: Vec<PathBuf> = std::fs::read_dir(&directory)?
+                    .filter_map(|entry| entry.ok().map(|e| e.path()))
+                    .filter(|path| {
+                        path.extension()
+                            .is_some_and(|ext| ext == "yml" || ext == "yaml")
+                    })
+                    .collect();
+                files.sort();
+                layers.extend(files);
+            }
+        }
+        Ok(layers)
+    }
+
+    /// Merge a defaults layer: new ecosystems are added, locations replace
+    /// existing locations with the same name and `disable` removes locations.
+    pub fn merge(&mut self, other: Defaults) {
+        self.ecosystems.extend(other.ecosystems);
+        for mut location in other.locations {
+            location.default = true;
+            match self.locations.iter_mut().find(|l| l.name == location.name) {
+                Some(existing) => *existing = location,
+                None => self.locations.push(location),
+            }
+        }
+        self.locations
+            .retain(|location| !other.disable.contains(&location.name));
+        self.disable.extend(other.disable);
     }
 
     pub fn get_locations(&self, ecosystem: impl Into<String>) -> Vec<LocationPattern> {
@@ -34,4 +101,121 @@ impl Defaults {
     pub fn get_languages(&self) -> Vec<String> {
         self.ecosystems.keys().cloned().collect()
     }
+
+    /// Detect the ecosystems used in a project by the number of versions
+    /// captured by each default location's patterns (in its `paths`, without
+    /// its excludes).
+    ///
+    /// The repository is not known yet, so repository placeholders match any
+    /// repository.
+    pub fn detect(&self, root: &Path) -> Result<Vec<(String, usize)>> {
+        let mut detected = Vec::new();
+        let mut languages = self.get_languages();
+        languages.sort();
+
+        for language in languages {
+            let aliases = self.ecosystems.get(&language).cloned().unwrap_or_default();
+            let mut config = Config {
+                locations: self
+                    .locations
+                    .iter()
+                    .filter(|loc| {
+                        loc.ecosystems
+                            .iter()
+                            .any(|eco| eco == &language || aliases.contains(eco))
+                    })
+                    .cloned()
+                    .collect(),
+                ..Default::default()
+            };
+            for location in config.locations.iter_mut() {
+                for pattern in location.patterns.iter_mut() {
+                    let pattern = pattern.pattern_mut();
+                    for placeholder in ["{repository}", "{repo}", "{owner}", "{name}", "{host}"] {
+                        *pattern = pattern.replace(placeholder, ANY_REPOSITORY);
+                    }
+                }
+            }
+            config.update_placeholders()?;
+
+            // Patterns of a location can capture the same version
+            let mut captured: BTreeSet<(PathBuf, usize, usize)> = BTreeSet::new();
+            for location in &config.locations {
+                let regexes = LocationPattern::regexes(&location.patterns)?;
+                for path in &location.paths {
+                    let pattern = root.join(path);
+                    for entry in glob::glob(&pattern.to_string_lossy())?.flatten() {
+                        if is_excluded(&location.excludes, root, &entry) {
+                            continue;
+                        }
+                        let Ok(content) = std::fs::read_to_string(&entry) else {
+                            continue;
+                        };
+                        for regex in &regexes {
+                            for capture in regex.captures_iter(&content) {
+                                if let Some(value) = capture.get(1) {
+                                    captured.insert((entry.clone(), value.start(), value.end()));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            debug!(
+                "Detected {} versions for ecosystem: {}",
+                captured.len(),
+                language
+            );
+            if !captured.is_empty() {
+                detected.push((language, captured.len()));
+            }
+        }
+        Ok(detected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_layer() {
+        let mut defaults = serde_yaml::from_str::<Defaults>(DEFAULTS).unwrap();
+        let layer = serde_yaml::from_str::<Defaults>(
+            "ecosystems:\n  Go:\n    - Golang\nlocations:\n  - name: \"Rust Cargo\"\n    paths: [Cargo.toml]\n    patterns: ['version = \"{version}\"']\ndisable:\n  - Documentation\n",
+        )
+        .unwrap();
+        defaults.merge(layer);
+
+        assert!(defaults.ecosystems.contains_key("Go"));
+        assert!(defaults.locations.iter().all(|l| l.name != "Documentation"));
+        let cargo = defaults
+            .locations
+            .iter()
+            .find(|l| l.name == "Rust Cargo")
+            .unwrap();
+        assert_eq!(cargo.paths, vec![PathBuf::from("Cargo.toml")]);
+        assert!(cargo.excludes.is_empty());
+    }
+
+    #[test]
+    fn test_detect() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        let cargo = "[package]\nversion = \"1.2.3\"\n";
+        std::fs::write(root.join("Cargo.toml"), cargo).unwrap();
+        std::fs::write(root.join("target/Cargo.toml"), cargo).unwrap();
+        std::fs::write(root.join("Dockerfile"), "FROM ghcr.io/acme/api:1.2.3\n").unwrap();
+        // Files without a version do not count
+        std::fs::write(root.join("pyproject.toml"), "[project]\n").unwrap();
+
+        let detected = Defaults::load_from(&[]).unwrap().detect(root).unwrap();
+
+        assert_eq!(
+            detected,
+            vec![("Containers".to_string(), 1), ("Rust".to_string(), 1)]
+        );
+    }
 }
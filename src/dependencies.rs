@@ -0,0 +1,487 @@
+//! Dependency graph of the packages in a monorepo / workspace.
+//!
+//! The graph is built from the manifests the default locations already know
+//! about (Cargo, NPM and Python) so bumping a package can update the version
+//! requirements of its dependents and cascade bumps in topological order.
+use anyhow::Result;
+use log::debug;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::ops::Range;
+use std::path::PathBuf;
+
+use crate::config::Target;
+use crate::rollback::Journal;
+
+lazy_static::lazy_static! {
+    /// Version inside a requirement (`^1.2`, `>=1.2.0`, `~1`)
+    static ref REQUIREMENT_VERSION: Regex =
+        Regex::new(r"[0-9]+(?:\.[0-9]+){0,2}(?:-[0-9A-Za-z.-]+)?").unwrap();
+    static ref CARGO_NAME: Regex =
+        Regex::new(r#"(?ms)^\[package\][^\[]*?^name\s*=\s*"([^"]+)""#).unwrap();
+    static ref NPM_NAME: Regex = Regex::new(r#""name"\s*:\s*"([^"]+)""#).unwrap();
+    static ref PYTHON_NAME: Regex =
+        Regex::new(r#"(?ms)^\[(?:project|tool\.poetry)\][^\[]*?^name\s*=\s*"([^"]+)""#).unwrap();
+    /// Tables of (build) dependencies, dev-dependencies are not included
+    static ref CARGO_DEPENDENCIES: Regex =
+        Regex::new(r"(?m)^\[(?:workspace\.|target\.[^\]\n]+\.)?(?:build-)?dependencies\]").unwrap();
+    static ref NPM_DEPENDENCIES: Regex =
+        Regex::new(r#""(?:dependencies|optionalDependencies)"\s*:\s*\{[^}]*\}"#).unwrap();
+    static ref PYTHON_DEPENDENCIES: Regex =
+        Regex::new(r#"(?m)^dependencies\s*=\s*\[(?:[^\]"']|"[^"]*"|'[^']*')*\]"#).unwrap();
+    static ref POETRY_DEPENDENCIES: Regex =
+        Regex::new(r"(?m)^\[tool\.poetry\.dependencies\]").unwrap();
+    static ref TABLE: Regex = Regex::new(r"(?m)^\s*\[").unwrap();
+}
+
+/// Manifest file formats which declare dependencies
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestKind {
+    /// Rust `Cargo.toml`
+    Cargo,
+    /// Node `package.json`
+    Npm,
+    /// Python `pyproject.toml`
+    Python,
+}
+
+impl ManifestKind {
+    /// All manifest kinds
+    pub const ALL: [ManifestKind; 3] =
+        [ManifestKind::Cargo, ManifestKind::Npm, ManifestKind::Python];
+
+    /// File name of the manifest in the package root
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ManifestKind::Cargo => "Cargo.toml",
+            ManifestKind::Npm => "package.json",
+            ManifestKind::Python => "pyproject.toml",
+        }
+    }
+
+    /// Name of the package declared in the manifest
+    pub fn package_name(&self, content: &str) -> Option<String> {
+        let regex = match self {
+            ManifestKind::Cargo => &*CARGO_NAME,
+            ManifestKind::Npm => &*NPM_NAME,
+            ManifestKind::Python => &*PYTHON_NAME,
+        };
+        regex.captures(content).map(|c| c[1].to_string())
+    }
+
+    /// Parts of a manifest declaring the dependencies a package needs to be
+    /// built and run. Dev and peer dependencies are left out, as they can form
+    /// cycles.
+    pub fn dependency_sections(&self, content: &str) -> Vec<Range<usize>> {
+        match self {
+            ManifestKind::Cargo => tables(content, &CARGO_DEPENDENCIES),
+            ManifestKind::Npm => NPM_DEPENDENCIES
+                .find_iter(content)
+                .map(|m| m.range())
+                .collect(),
+            ManifestKind::Python => {
+                let mut sections: Vec<Range<usize>> = PYTHON_DEPENDENCIES
+                    .find_iter(content)
+                    .map(|m| m.range())
+                    .collect();
+                sections.extend(tables(content, &POETRY_DEPENDENCIES));
+                sections
+            }
+        }
+    }
+
+    /// Patterns which capture the requirement (`req` group) on a dependency
+    pub fn requirement_patterns(&self, dependency: &str) -> Vec<Regex> {
+        let name = regex::escape(dependency);
+        let patterns = match self {
+            ManifestKind::Cargo => vec![
+                format!(r#"(?m)^{name}\s*=\s*"(?P<req>[^"]+)""#),
+                format!(r#"(?m)^{name}\s*=\s*\{{[^}}\n]*?version\s*=\s*"(?P<req>[^"]+)""#),
+            ],
+            ManifestKind::Npm => vec![format!(r#""{name}"\s*:\s*"(?P<req>[^"]+)""#)],
+            ManifestKind::Python => vec![
+                format!(r#"["']{name}\s*(?P<req>[=<>!~^]=?\s*[0-9][^"';]*)["';]"#),
+                format!(r#"(?m)^{name}\s*=\s*"(?P<req>[^"]+)""#),
+            ],
+        };
+        patterns
+            .iter()
+            .filter_map(|pattern| Regex::new(pattern).ok())
+            .collect()
+    }
+}
+
+/// Contents of the TOML tables with a matching header
+fn tables(content: &str, header: &Regex) -> Vec<Range<usize>> {
+    header
+        .find_iter(content)
+        .map(|found| {
+            let end = TABLE
+                .find_at(content, found.end())
+                .map_or(content.len(), |next| next.start());
+            found.end()..end
+        })
+        .collect()
+}
+
+/// Package in the dependency graph
+#[derive(Debug, Clone)]
+pub struct PackageNode {
+    /// Names of the package declared in its manifests
+    pub names: Vec<String>,
+    /// Manifests in the package root
+    pub manifests: Vec<(ManifestKind, PathBuf)>,
+    /// Packages (configuration names) this package depends on, without dev
+    /// dependencies
+    pub dependencies: BTreeSet<String>,
+}
+
+/// Requirement on a package in the manifest of another package
+#[derive(Debug, Clone)]
+struct Requirement {
+    path: PathBuf,
+    start: usize,
+    end: usize,
+    old: String,
+    /// If the requirement is a (build) dependency, not a dev dependency
+    normal: bool,
+}
+
+/// Requirement on a package which was updated in a dependent manifest
+#[derive(Debug, Clone)]
+pub struct RequirementUpdate {
+    /// Manifest which was updated
+    pub path: PathBuf,
+    /// Dependent package
+    pub dependent: String,
+    /// Previous requirement
+    pub old: String,
+    /// New requirement
+    pub new: String,
+}
+
+/// Internal dependency graph of the packages
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    nodes: BTreeMap<String, PackageNode>,
+}
+
+impl DependencyGraph {
+    /// Build the graph from the manifests in the root of every package
+    pub fn load(targets: &[Target]) -> Result<Self> {
+        let mut nodes = BTreeMap::new();
+        for target in targets {
+            let Some(package) = &target.package else {
+                continue;
+            };
+            let mut node = PackageNode {
+                names: Vec::new(),
+                manifests: Vec::new(),
+                dependencies: BTreeSet::new(),
+            };
+            for kind in ManifestKind::ALL {
+                let path = target.root.join(kind.file_name());
+                if !path.exists() {
+                    continue;
+                }
+                if let Some(name) = kind.package_name(&std::fs::read_to_string(&path)?) {
+                    if !node.names.contains(&name) {
+                        node.names.push(name);
+                    }
+                }
+                node.manifests.push((kind, path));
+            }
+            if node.names.is_empty() {
+                node.names.push(package.clone());
+            }
+            nodes.insert(package.clone(), node);
+        }
+
+        let mut graph = Self { nodes };
+        let packages: Vec<String> = graph.nodes.keys().cloned().collect();
+        for package in &packages {
+            let mut dependencies = BTreeSet::new();
+            for dependency in &packages {
+                if dependency != package
+                    && graph
+                        .requirements(package, dependency)?
+                        .iter()
+                        .any(|requirement| requirement.normal)
+                {
+                    dependencies.insert(dependency.clone());
+                }
+            }
+            debug!("Package `{}` depends on: {:?}", package, dependencies);
+            if let Some(node) = graph.nodes.get_mut(package) {
+                node.dependencies = dependencies;
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Packages which a package directly depends on
+    pub fn dependencies(&self, package: &str) -> Vec<&str> {
+        self.nodes
+            .get(package)
+            .map(|node| node.dependencies.iter().map(|d| d.as_str()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Packages ordered so every package comes after its dependencies
+    pub fn topological(&self) -> Result<Vec<String>> {
+        let mut order: Vec<String> = Vec::new();
+        let mut remaining: BTreeSet<&String> = self.nodes.keys().collect();
+
+        while !remaining.is_empty() {
+            let ready: Vec<&String> = remaining
+                .iter()
+                .filter(|package| {
+                    self.nodes[**package]
+                        .dependencies
+                        .iter()
+                        .all(|dependency| order.contains(dependency))
+                })
+                .cloned()
+                .collect();
+            if ready.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "Dependency cycle between packages: {}",
+                    remaining
+                        .iter()
+                        .map(|p| p.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+            for package in ready {
+                remaining.remove(package);
+                order.push(package.clone());
+            }
+        }
+        Ok(order)
+    }
+
+    /// Requirements in the manifests of `dependent` on `dependency`
+    fn requirements(&self, dependent: &str, dependency: &str) -> Result<Vec<Requirement>> {
+        let (Some(node), Some(dep)) = (self.nodes.get(dependent), self.nodes.get(dependency))
+        else {
+            return Ok(Vec::new());
+        };
+        let mut requirements = Vec::new();
+        for (kind, path) in &node.manifests {
+            let content = std::fs::read_to_string(path)?;
+            let sections = kind.dependency_sections(&content);
+            for name in &dep.names {
+                for regex in kind.requirement_patterns(name) {
+                    for capture in regex.captures_iter(&content) {
+                        let req = capture.name("req").unwrap();
+                        if REQUIREMENT_VERSION.is_match(req.as_str()) {
+                            requirements.push(Requirement {
+                                path: path.clone(),
+                                start: req.start(),
+                                end: req.end(),
+                                old: req.as_str().to_string(),
+                                normal: sections
+                                    .iter()
+                                    .any(|section| section.contains(&req.start())),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+        Ok(requirements)
+    }
+
+    /// Update the requirements (including dev dependencies) of every package
+    /// on a package to a new version, the manifests are written with the
+    /// journal of the bump
+    pub fn update_requirements(
+        &self,
+        package: &str,
+        version: &semver::Version,
+        journal: &Journal,
+    ) -> Result<Vec<RequirementUpdate>> {
+        let mut updates = Vec::new();
+        for dependent in self.nodes.keys().filter(|name| *name != package) {
+            let mut files: BTreeMap<PathBuf, Vec<(usize, usize, String)>> = BTreeMap::new();
+            for Requirement {
+                path,
+                start,
+                end,
+                old,
+                ..
+            } in self.requirements(dependent, package)?
+            {
+                let Some(new) = update_requirement(&old, version) else {
+                    continue;
+                };
+                if new == old {
+                    continue;
+                }
+                files
+                    .entry(path.clone())
+                    .or_default()
+                    .push((start, end, new.clone()));
+                updates.push(RequirementUpdate {
+                    path,
+                    dependent: dependent.to_string(),
+                    old,
+                    new,
+                });
+            }
+
+            for (path, mut replacements) in files {
+                let mut content = std::fs::read_to_string(&path)?;
+                replacements.sort_by_key(|(start, ..)| *start);
+                replacements.dedup_by_key(|(start, ..)| *start);
+                for (start, end, new) in replacements.iter().rev() {
+                    content.replace_range(*start..*end, new);
+                }
+                journal.write(&path, content)?;
+            }
+        }
+        Ok(updates)
+    }
+}
+
+/// Update the version in a requirement, keeping the operator and precision
+/// (`^1.2` with `1.3.0` becomes `^1.3`).
+pub(crate) fn update_requirement(requirement: &str, version: &semver::Version) -> Option<String> {
+    let found = REQUIREMENT_VERSION.find(requirement)?;
+    let components = found
+        .as_str()
+        .split('-')
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .count();
+
+    let new_version = match components {
+        1 => version.major.to_string(),
+        2 => format!("{}.{}", version.major, version.minor),
+        _ => version.to_string(),
+    };
+    let mut requirement = requirement.to_string();
+    requirement.replace_range(found.range(), &new_version);
+    Some(requirement)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_update_requirement() {
+        let version = semver::Version::new(1, 3, 0);
+        assert_eq!(update_requirement("^1.2", &version).unwrap(), "^1.3");
+        assert_eq!(update_requirement(">=1.2.4", &version).unwrap(), ">=1.3.0");
+        assert_eq!(update_requirement("1", &version).unwrap(), "1");
+        assert_eq!(
+            update_requirement("~1.2.0-beta.1", &version).unwrap(),
+            "~1.3.0"
+        );
+        assert!(update_requirement("workspace:*", &version).is_none());
+    }
+
+    #[test]
+    fn test_dev_dependency_cycle() {
+        let directory = tempfile::tempdir().unwrap();
+        let manifest = |name: &str, dependencies: &str| {
+            let root = directory.path().join(name);
+            std::fs::create_dir_all(&root).unwrap();
+            std::fs::write(
+                root.join("Cargo.toml"),
+                format!("[package]\nname = \"{name}\"\nversion = \"1.0.0\"\n\n{dependencies}"),
+            )
+            .unwrap();
+            Target {
+                package: Some(name.to_string()),
+                root,
+                config: Default::default(),
+            }
+        };
+        let targets = vec![
+            manifest(
+                "a",
+                "[dependencies]\nb = { version = \"1.0\", path = \"../b\" }\n",
+            ),
+            manifest(
+                "b",
+                "[dependencies]\nserde = \"1\"\n\n[dev-dependencies]\na = { version = \"1.0\", path = \"../a\" }\n",
+            ),
+        ];
+
+        let graph = DependencyGraph::load(&targets).unwrap();
+        assert_eq!(graph.dependencies("a"), vec!["b"]);
+        assert!(graph.dependencies("b").is_empty());
+        assert_eq!(graph.topological().unwrap(), vec!["b", "a"]);
+        // Dev dependencies still have their requirements updated
+        let journal = Journal::default();
+        let updates = graph
+            .update_requirements("a", &semver::Version::new(2, 0, 0), &journal)
+            .unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(
+            (updates[0].old.as_str(), updates[0].new.as_str()),
+            ("1.0", "2.0")
+        );
+        assert_eq!(
+            journal.files_since(0),
+            vec![targets[1].root.join("Cargo.toml")]
+        );
+    }
+
+    #[test]
+    fn test_dependency_sections() {
+        let npm = r#"{"dependencies": {"a": "^1.0.0"}, "devDependencies": {"b": "^1.0.0"}}"#;
+        let sections = ManifestKind::Npm.dependency_sections(npm);
+        assert!(
+            sections
+                .iter()
+                .any(|s| s.contains(&npm.find("\"a\"").unwrap()))
+        );
+        assert!(
+            !sections
+                .iter()
+                .any(|s| s.contains(&npm.find("\"b\"").unwrap()))
+        );
+
+        let python = "[project]\ndependencies = [\"a>=1.0\", \"c[x]>=2\"]\n\n[project.optional-dependencies]\ndev = [\"b>=1.0\"]\n";
+        let sections = ManifestKind::Python.dependency_sections(python);
+        assert!(
+            sections
+                .iter()
+                .any(|s| s.contains(&python.find("a>=").unwrap()))
+        );
+        assert!(
+            !sections
+                .iter()
+                .any(|s| s.contains(&python.find("b>=").unwrap()))
+        );
+    }
+
+    #[test]
+    fn test_requirement_patterns() {
+        let cargo = "[dependencies]\na = { version = \"1.2\", path = \"../a\" }\nab = \"2\"\n";
+        let captures: Vec<String> = ManifestKind::Cargo
+            .requirement_patterns("a")
+            .iter()
+            .flat_map(|r| r.captures_iter(cargo).map(|c| c["req"].to_string()))
+            .collect();
+        assert_eq!(captures, vec!["1.2"]);
+
+        let npm = r#"{"name": "b", "dependencies": {"a": "^1.2.0"}}"#;
+        let regex = &ManifestKind::Npm.requirement_patterns("a")[0];
+        assert_eq!(&regex.captures(npm).unwrap()["req"], "^1.2.0");
+
+        let python = "dependencies = [\"a>=1.2.0\", \"requests\"]\n";
+        let regex = &ManifestKind::Python.requirement_patterns("a")[0];
+        assert_eq!(&regex.captures(python).unwrap()["req"], ">=1.2.0");
+
+        assert_eq!(
+            ManifestKind::Cargo.package_name("[package]\nname = \"a\"\nversion = \"1.0.0\"\n"),
+            Some("a".to_string())
+        );
+    }
+}
@@ -6,6 +6,18 @@ pub enum Error {
     #[error("Invalid Language: {0}")]
     InvalidLanguage(String),
 
+    #[error("Pattern has no capture group: {0}")]
+    MissingCaptureGroup(String),
+
+    #[error("Location `{0}` matched {1} times, expected {2} matches")]
+    UnexpectedMatchCount(String, usize, String),
+
+    #[error("Location `{0}` has an invalid date format `{1}`")]
+    InvalidDateFormat(String, String),
+
+    #[error("Location `{0}` is a counter, counters can not be bound to a version stream")]
+    StreamCounter(String),
+
     #[error("Unknown Error: {0}")]
     Unknown(String),
 }
@@ -0,0 +1,211 @@
+//! GitHub Actions integration.
+//!
+//! When running inside GitHub Actions (`GITHUB_ACTIONS=true`) the results of a
+//! command are written to the step outputs (`$GITHUB_OUTPUT`), appended to the
+//! job summary (`$GITHUB_STEP_SUMMARY`) and drift is reported as annotations.
+use anyhow::Result;
+use log::debug;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::reports::relative;
+use crate::workflows::Drift;
+
+/// Delimiter used for multiline step outputs
+const OUTPUT_DELIMITER: &str = "PRM_OUTPUT_EOF";
+
+/// Result of a command reported to GitHub Actions
+#[derive(Debug, Clone, Default)]
+pub struct ActionReport {
+    /// Command which was run (`display`, `check`, `sync` or `bump`)
+    pub command: String,
+    /// Version before the command
+    pub old_version: Option<String>,
+    /// Version after the command
+    pub new_version: Option<String>,
+    /// Files which were updated
+    pub changed_files: Vec<PathBuf>,
+    /// Locations which differ from the version (only for `check`)
+    pub drift: Option<Vec<Drift>>,
+}
+
+/// If running inside GitHub Actions
+pub fn is_actions() -> bool {
+    std::env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true")
+}
+
+impl ActionReport {
+    /// Write the outputs, summary and annotations of the report
+    pub fn write(&self, root: &Path) -> Result<()> {
+        let root = &root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        if let Some(drift) = &self.drift {
+            for item in drift {
+                println!("{}", annotation(root, item));
+            }
+        }
+        if let Ok(path) = std::env::var("GITHUB_OUTPUT") {
+            append(&path, &self.outputs(root))?;
+        }
+        if let Ok(path) = std::env::var("GITHUB_STEP_SUMMARY") {
+            append(&path, &self.summary(root))?;
+        }
+        Ok(())
+    }
+
+    /// Step outputs (`key=value` and multiline `key<<EOF` entries)
+    fn outputs(&self, root: &Path) -> String {
+        let mut outputs = String::new();
+        outputs.push_str(&format!(
+            "old-version={}\n",
+            self.old_version.clone().unwrap_or_default()
+        ));
+        outputs.push_str(&format!(
+            "new-version={}\n",
+            self.new_version.clone().unwrap_or_default()
+        ));
+
+        let files: Vec<String> = self
+            .changed_files
+            .iter()
+            .map(|path| relative(root, path))
+            .collect();
+        outputs.push_str(&format!(
+            "changed-files<<{OUTPUT_DELIMITER}\n{}\n{OUTPUT_DELIMITER}\n",
+            files.join("\n")
+        ));
+
+        let drift = self.drift.as_ref().is_some_and(|drift| !drift.is_empty());
+        outputs.push_str(&format!("drift={drift}\n"));
+        outputs
+    }
+
+    /// Markdown job summary
+    fn summary(&self, root: &Path) -> String {
+        let mut summary = format!("## Patch Release Me: `{}`\n\n", self.command);
+        summary.push_str("| Old Version | New Version | Changed Files |\n");
+        summary.push_str("| --- | --- | --- |\n");
+        summary.push_str(&format!(
+            "| {} | {} | {} |\n\n",
+            self.old_version.as_deref().unwrap_or("-"),
+            self.new_version.as_deref().unwrap_or("-"),
+            self.changed_files.len()
+        ));
+
+        if !self.changed_files.is_empty() {
+            summary.push_str("| File |\n| --- |\n");
+            for path in &self.changed_files {
+                summary.push_str(&format!("| `{}` |\n", relative(root, path)));
+            }
+            summary.push('\n');
+        }
+
+        match &self.drift {
+            Some(drift) if drift.is_empty() => {
+                summary.push_str("✅ All locations match the version\n\n");
+            }
+            Some(drift) => {
+                summary.push_str(&format!(
+                    "❌ {} values do not match the version\n\n",
+                    drift.len()
+                ));
+                summary.push_str("| Location | File | Line | Found | Expected |\n");
+                summary.push_str("| --- | --- | --- | --- | --- |\n");
+                for item in drift {
+                    summary.push_str(&format!(
+                        "| {} | `{}` | {} | `{}` | `{}` |\n",
+                        item.location,
+                        relative(root, &item.path),
+                        item.line,
+                        item.found,
+                        item.expected
+                    ));
+                }
+                summary.push('\n');
+            }
+            None => {}
+        }
+        summary
+    }
+}
+
+/// Workflow command annotating the file and line of a drift
+fn annotation(root: &Path, drift: &Drift) -> String {
+    format!(
+        "::error file={},line={},col={},title={}::{}",
+        escape_property(&relative(root, &drift.path)),
+        drift.line,
+        drift.column,
+        escape_property(&format!("Version drift ({})", drift.location)),
+        escape_data(&format!(
+            "Found `{}`, expected `{}`",
+            drift.found, drift.expected
+        ))
+    )
+}
+
+/// Escape the message of a workflow command
+fn escape_data(value: &str) -> String {
+    value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property of a workflow command
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn append(path: &str, content: &str) -> Result<()> {
+    debug!("Writing GitHub Actions file: {}", path);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    file.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn drift() -> Drift {
+        Drift {
+            location: "Cargo, Docs".to_string(),
+            path: PathBuf::from("/repo/Cargo.toml"),
+            line: 3,
+            column: 12,
+            found: "1.2.3".to_string(),
+            expected: "1.3.0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_annotation() {
+        assert_eq!(
+            annotation(Path::new("/repo"), &drift()),
+            "::error file=Cargo.toml,line=3,col=12,title=Version drift (Cargo%2C Docs)::Found `1.2.3`, expected `1.3.0`"
+        );
+    }
+
+    #[test]
+    fn test_outputs() {
+        let report = ActionReport {
+            command: "check".to_string(),
+            old_version: Some("1.3.0".to_string()),
+            new_version: Some("1.3.0".to_string()),
+            changed_files: vec![],
+            drift: Some(vec![drift()]),
+        };
+        assert_eq!(
+            report.outputs(Path::new("/repo")),
+            "old-version=1.3.0\nnew-version=1.3.0\nchanged-files<<PRM_OUTPUT_EOF\n\nPRM_OUTPUT_EOF\ndrift=true\n"
+        );
+        assert!(
+            report
+                .summary(Path::new("/repo"))
+                .contains("| Cargo, Docs | `Cargo.toml` | 3 | `1.2.3` | `1.3.0` |")
+        );
+    }
+}
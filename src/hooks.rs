@@ -0,0 +1,269 @@
+//! Shell commands run before and after a bump.
+//!
+//! Hooks run in the project (or package) root with the old and new version
+//! and the changed files in the environment. Their output is streamed to the
+//! terminal and a hook which fails or runs longer than its timeout aborts the
+//! bump.
+use anyhow::{Result, anyhow};
+use console::style;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::time::{Duration, Instant};
+
+/// Default timeout of a hook in seconds
+pub const DEFAULT_TIMEOUT: u64 = 300;
+
+/// Hooks of a project
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Hooks {
+    /// Run before any file is patched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_bump: Vec<Hook>,
+    /// Run after the files are patched
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_bump: Vec<Hook>,
+    /// Run after the changes are committed (`bump --commit`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_commit: Vec<Hook>,
+    /// Timeout of every hook in seconds (default: 300)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+/// Hook command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Hook {
+    /// Shell command
+    Command(String),
+    /// Shell command with its own timeout
+    Detailed {
+        /// Shell command
+        run: String,
+        /// Timeout in seconds
+        timeout: Option<u64>,
+    },
+}
+
+/// Stage of a bump a hook runs at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookStage {
+    PreBump,
+    PostBump,
+    PostCommit,
+}
+
+impl std::fmt::Display for HookStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HookStage::PreBump => write!(f, "pre_bump"),
+            HookStage::PostBump => write!(f, "post_bump"),
+            HookStage::PostCommit => write!(f, "post_commit"),
+        }
+    }
+}
+
+impl Hook {
+    fn command(&self) -> &str {
+        match self {
+            Hook::Command(run) | Hook::Detailed { run, .. } => run,
+        }
+    }
+}
+
+impl Hooks {
+    /// If no hooks are configured
+    pub fn is_empty(&self) -> bool {
+        self.pre_bump.is_empty() && self.post_bump.is_empty() && self.post_commit.is_empty()
+    }
+
+    /// Merge other hooks (from an extending configuration) into these hooks
+    pub fn merge(&mut self, other: Hooks) {
+        self.pre_bump.extend(other.pre_bump);
+        self.post_bump.extend(other.post_bump);
+        self.post_commit.extend(other.post_commit);
+        self.timeout = other.timeout.or(self.timeout);
+    }
+
+    /// Hooks of a stage
+    pub fn stage(&self, stage: HookStage) -> &[Hook] {
+        match stage {
+            HookStage::PreBump => &self.pre_bump,
+            HookStage::PostBump => &self.post_bump,
+            HookStage::PostCommit => &self.post_commit,
+        }
+    }
+
+    /// Run the hooks of a stage in order, stopping at the first failure
+    pub fn run(&self, stage: HookStage, root: &Path, env: &[(&str, String)]) -> Result<()> {
+        for hook in self.stage(stage) {
+            let timeout = match hook {
+                Hook::Detailed {
+                    timeout: Some(timeout),
+                    ..
+                } => *timeout,
+                _ => self.timeout.unwrap_or(DEFAULT_TIMEOUT),
+            };
+            println!(
+                "  {} {} {}",
+                style("⚙").cyan(),
+                style(stage).dim(),
+                style(hook.command()).bold()
+            );
+            run_command(hook.command(), root, env, Duration::from_secs(timeout))
+                .map_err(|e| anyhow!("{} hook `{}` failed: {}", stage, hook.command(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Shell (`sh -c` or `cmd /C` on Windows) running a command
+pub(crate) fn shell(command: &str) -> Command {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+/// Run a shell command, streaming its output, and wait for it to finish
+fn run_command(
+    command: &str,
+    root: &Path,
+    env: &[(&str, String)],
+    timeout: Duration,
+) -> Result<()> {
+    let mut shell = shell(command);
+    shell
+        .current_dir(root)
+        .envs(env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit());
+    let mut child = spawn(&mut shell)?;
+
+    let status = wait(&mut child, timeout)?;
+    debug!("Hook `{}` exited with {}", command, status);
+    match status.success() {
+        true => Ok(()),
+        false => Err(anyhow!("exited with {}", status)),
+    }
+}
+
+/// Spawn a command in its own process group, so a timeout also stops the
+/// processes it started
+pub(crate) fn spawn(command: &mut Command) -> std::io::Result<Child> {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    command.spawn()
+}
+
+/// Wait for a spawned command, killing it when it runs longer than the timeout
+pub(crate) fn wait(child: &mut Child, timeout: Duration) -> Result<ExitStatus> {
+    let started = Instant::now();
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if started.elapsed() >= timeout {
+            kill(child)?;
+            return Err(anyhow!("timed out after {}s", timeout.as_secs()));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Kill a command and every process in its process group
+fn kill(child: &mut Child) -> Result<()> {
+    #[cfg(unix)]
+    {
+        let status = Command::new("kill")
+            .args(["-KILL", "--", &format!("-{}", child.id())])
+            .stderr(Stdio::null())
+            .status();
+        debug!("Killed process group {}: {:?}", child.id(), status);
+    }
+    child.kill()?;
+    child.wait()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hooks_config() {
+        let hooks: Hooks = serde_yaml::from_str(
+            "pre_bump:\n  - cargo test\npost_bump:\n  - run: cargo generate-lockfile\n    timeout: 60\ntimeout: 10\n",
+        )
+        .unwrap();
+        assert_eq!(
+            hooks.pre_bump,
+            vec![Hook::Command("cargo test".to_string())]
+        );
+        assert_eq!(
+            hooks.post_bump,
+            vec![Hook::Detailed {
+                run: "cargo generate-lockfile".to_string(),
+                timeout: Some(60),
+            }]
+        );
+        assert_eq!(hooks.timeout, Some(10));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_hooks() {
+        let root = std::env::temp_dir();
+        let env = [("PRM_NEW_VERSION", "1.3.0".to_string())];
+        let hooks = Hooks {
+            pre_bump: vec![Hook::Command(
+                "test \"$PRM_NEW_VERSION\" = 1.3.0".to_string(),
+            )],
+            post_bump: vec![Hook::Command("exit 3".to_string())],
+            post_commit: vec![Hook::Detailed {
+                run: "sleep 5".to_string(),
+                timeout: Some(0),
+            }],
+            timeout: None,
+        };
+        assert!(hooks.run(HookStage::PreBump, &root, &env).is_ok());
+        assert!(hooks.run(HookStage::PostBump, &root, &env).is_err());
+
+        let error = hooks
+            .run(HookStage::PostCommit, &root, &env)
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("timed out"), "{error}");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_timeout_kills_process_group() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        let pid_file = root.join("sleep.pid");
+        let command = format!("sleep 30 & echo $! > {}; wait", pid_file.display());
+
+        let error = run_command(&command, root, &[], Duration::from_secs(1)).unwrap_err();
+        assert!(error.to_string().contains("timed out"));
+
+        let pid = std::fs::read_to_string(&pid_file).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        // The background process is gone (or a zombie waiting to be reaped)
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()));
+        assert!(
+            stat.as_ref().map_or(true, |stat| stat.contains(") Z ")),
+            "{stat:?}"
+        );
+    }
+}
@@ -1,18 +1,58 @@
 use crate::config::Config;
+use crate::remote::Remote;
 use crate::update_version;
+use crate::workflows::{Replacement, Workflow, common_version};
 use crate::{WorkflowMode, config::BumpMode, defaults::Defaults};
 use anyhow::{Context, Result, anyhow};
+use console::style;
 use dialoguer::theme::ColorfulTheme;
 use dialoguer::{FuzzySelect, Select};
-use log::debug;
+use log::{debug, warn};
+use std::io::IsTerminal;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
 
-pub fn select_mode(config: &Config) -> Result<WorkflowMode> {
+/// If prompting the user is disabled
+static NON_INTERACTIVE: AtomicBool = AtomicBool::new(false);
+
+/// Disable prompts when requested, when stdin is not a terminal or in CI
+pub fn set_non_interactive(non_interactive: bool) {
+    let ci = std::env::var("CI").is_ok_and(|ci| ci == "true" || ci == "1");
+    let enabled = non_interactive || ci || !std::io::stdin().is_terminal();
+    debug!("Non-interactive mode: {}", enabled);
+    NON_INTERACTIVE.store(enabled, Ordering::Relaxed);
+}
+
+/// If prompts can be shown
+pub fn is_interactive() -> bool {
+    !NON_INTERACTIVE.load(Ordering::Relaxed)
+}
+
+/// Fail with a helpful message instead of prompting in non-interactive mode
+pub(crate) fn ensure_interactive(missing: &str) -> Result<()> {
+    if NON_INTERACTIVE.load(Ordering::Relaxed) {
+        return Err(anyhow!(
+            "Cannot prompt in non-interactive mode: {}",
+            missing
+        ));
+    }
+    Ok(())
+}
+
+/// Select the mode to run, returns `true` with a bump which is reviewed in
+/// the full-screen UI
+pub fn select_mode(
+    config: &Config,
+    root: &Path,
+    defaults: &Defaults,
+) -> Result<(WorkflowMode, bool)> {
+    ensure_interactive("no subcommand given, use one of `init`, `display`, `bump` or `sync`")?;
     println!("\n🚀 Welcome to Patch Release Me - Interactive Mode\n");
 
     let mut modes = Vec::new();
     let mut descriptions = Vec::new();
 
-    if !config.version.is_some() {
+    if config.version.is_none() {
         modes.push("Init");
         descriptions.push("Initialize new .release.yml configuration");
     }
@@ -20,6 +60,8 @@ pub fn select_mode(config: &Config) -> Result<WorkflowMode> {
     descriptions.push("Preview version changes (dry-run)");
     modes.push("Bump");
     descriptions.push("Increment version and update files");
+    modes.push("Review");
+    descriptions.push("Bump after reviewing every change in a full-screen UI");
     modes.push("Sync");
     descriptions.push("Apply current version to all files");
 
@@ -39,28 +81,37 @@ pub fn select_mode(config: &Config) -> Result<WorkflowMode> {
     let text = modes.get(selection).ok_or(anyhow!("Invalid selection"))?;
 
     match *text {
-        "Init" => Ok(interactive_init()?),
-        "Display" => Ok(WorkflowMode::Display),
+        "Init" => Ok((interactive_init(config, root, defaults)?, false)),
+        "Display" => Ok((WorkflowMode::Display, false)),
         "Sync" => {
-            let version = new_version(config, &BumpMode::Version("0.0.0".to_string()))?;
-            Ok(WorkflowMode::Bump {
-                mode: BumpMode::Version(version.to_string()),
-                version,
-            })
+            let version = new_version(config, None, &BumpMode::Version("0.0.0".to_string()))?;
+            Ok((
+                WorkflowMode::Bump {
+                    mode: BumpMode::Version(version.to_string()),
+                    version,
+                    counter: config.counter,
+                },
+                false,
+            ))
         }
-        "Bump" => {
+        "Bump" | "Review" => {
             let bump_mode = select_bump_mode()?;
-            let version = new_version(config, &bump_mode)?;
-            Ok(WorkflowMode::Bump {
-                mode: bump_mode,
-                version,
-            })
+            let version = new_version(config, None, &bump_mode)?;
+            Ok((
+                WorkflowMode::Bump {
+                    mode: bump_mode,
+                    version,
+                    counter: config.counter.map(|c| c + 1),
+                },
+                *text == "Review",
+            ))
         }
         _ => Err(anyhow!("Invalid selection")),
     }
 }
 
-pub fn interactive_init() -> Result<WorkflowMode> {
+pub fn interactive_init(config: &Config, root: &Path, defaults: &Defaults) -> Result<WorkflowMode> {
+    ensure_interactive("use `init` with `--name`, `--version` and `--language-ecosystems`")?;
     println!("\n📝 Configuration Setup\n");
     println!("Let's create your .release.yml configuration file.\n");
 
@@ -69,9 +120,14 @@ pub fn interactive_init() -> Result<WorkflowMode> {
         .with_initial_text(find_project_name()?)
         .interact_text()?;
 
+    // Propose the version already used in the project
+    let detected = detect_initial_version(root, config, &[], defaults)?
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| "0.1.0".to_string());
+
     let version = dialoguer::Input::<String>::new()
         .with_prompt("Initial version (semver format)")
-        .default("0.1.0".to_string())
+        .default(detected)
         .validate_with(|input: &String| -> Result<(), &str> {
             if semver::Version::parse(input).is_ok() {
                 Ok(())
@@ -86,29 +142,43 @@ pub fn interactive_init() -> Result<WorkflowMode> {
         .default(true)
         .interact()?;
 
-    let defaults = dialoguer::Confirm::new()
+    let inline = dialoguer::Confirm::new()
         .with_prompt("Inline ecosystem patterns into config? (Makes config portable)")
         .default(false)
         .interact()?;
 
-    let language_ecosystems = if defaults {
+    let language_ecosystems = if inline {
         println!(
             "\n🔧 Select your project's ecosystems (use Space to select, Enter to confirm):\n"
         );
 
-        let defaults = Defaults::load()?;
         let mut lang_list = defaults.get_languages();
         lang_list.sort();
 
+        // Preselect the ecosystems detected in the project
+        let detected = defaults.detect(root)?;
+        let items: Vec<String> = lang_list
+            .iter()
+            .map(|lang| match detected.iter().find(|(l, _)| l == lang) {
+                Some((_, count)) => format!("{lang} ({count} versions detected)"),
+                None => lang.clone(),
+            })
+            .collect();
+        let preselected: Vec<bool> = lang_list
+            .iter()
+            .map(|lang| detected.iter().any(|(l, _)| l == lang))
+            .collect();
+
         let lang_index = dialoguer::MultiSelect::new()
             .with_prompt("Language ecosystems")
-            .items(&lang_list)
+            .items(&items)
+            .defaults(&preselected)
             .interact()?;
 
         let selected: Vec<String> = lang_list
             .iter()
             .enumerate()
-            .filter_map(|(i, &ref lang)| {
+            .filter_map(|(i, lang)| {
                 if lang_index.contains(&i) {
                     Some(lang.to_string())
                 } else {
@@ -130,45 +200,72 @@ pub fn interactive_init() -> Result<WorkflowMode> {
     debug!("Language Ecosystems: {:?}", language_ecosystems);
 
     // Auto-detect Git repository
-    let repository: Option<String> = match std::process::Command::new("git")
-        .arg("remote")
-        .arg("get-url")
-        .arg("origin")
-        .output()
-    {
-        Ok(output) => {
-            let mut repo = String::from_utf8(output.stdout)?.trim().to_string();
-            if repo.ends_with(".git") {
-                repo = repo.trim_end_matches(".git").to_string();
-            }
-            if repo.starts_with("git@") {
-                repo = repo.trim_start_matches("git@").to_string();
-                repo = repo.replace("github.com:", "");
-            }
-            if repo.starts_with("https://github.com/") {
-                repo = repo.trim_start_matches("https://github.com/").to_string();
-            }
-            println!("\n✓ Git repository detected: {}", repo);
-            Some(repo)
-        }
-        Err(_) => {
-            println!("\n⚠️  No Git repository detected");
-            None
-        }
-    };
+    let remote = Remote::detect(root);
+    match &remote {
+        Some(remote) => println!(
+            "\n✓ Git repository detected: {} ({})",
+            remote.repository, remote.host
+        ),
+        None => println!("\n⚠️  No Git repository detected"),
+    }
 
     println!("\n✅ Configuration complete! Creating .release.yml...\n");
 
     Ok(WorkflowMode::Init {
         name: Some(name),
         version: Some(version),
-        repository,
+        repository: remote.as_ref().map(|r| r.repository.clone()),
+        host: remote.map(|r| r.host),
         language_ecosystems,
         enable_defaults: Some(use_defaults),
     })
 }
 
+/// Detect the initial version of a project by scanning the configured (and
+/// default) locations for the versions they capture.
+pub fn detect_initial_version(
+    root: &Path,
+    config: &Config,
+    ecosystems: &[String],
+    defaults: &Defaults,
+) -> Result<Option<semver::Version>> {
+    let mut scan_config = Config {
+        name: config.name.clone(),
+        repository: config.repository.clone(),
+        ecosystems: ecosystems.to_vec(),
+        locations: config.locations.clone(),
+        ..Default::default()
+    };
+    scan_config.resolve(root, &[], defaults)?;
+
+    let found = Workflow::init()
+        .root(root.to_path_buf())?
+        .locations(scan_config.stream_locations(None))?
+        .build()
+        .scan()?;
+    let Some(version) = common_version(&found) else {
+        return Ok(None);
+    };
+
+    println!(
+        "\n{} Detected version: {}",
+        style("🔍").cyan(),
+        style(&version).green().bold()
+    );
+    for (path, value) in &found {
+        if semver::Version::parse(value).ok().as_ref() != Some(&version) {
+            warn!(
+                "Version `{}` in {} does not match the detected version",
+                value,
+                path.display()
+            );
+        }
+    }
+    Ok(Some(version))
+}
+
 pub fn select_bump_mode() -> Result<BumpMode> {
+    ensure_interactive("use `bump` with `--mode` or `--set-version`")?;
     println!("\n📦 Version Bump Strategy\n");
 
     let items = vec![
@@ -215,7 +312,125 @@ pub fn select_bump_mode() -> Result<BumpMode> {
     }
 }
 
+/// Decision for a single planned replacement
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Approval {
+    /// Apply the replacement
+    Accept,
+    /// Skip the replacement
+    Skip,
+    /// Skip this and every remaining replacement in the file
+    SkipFile,
+    /// Skip the file and exclude it from the location
+    Exclude,
+}
+
+/// Show a planned replacement with context lines and ask the user to approve it
+pub fn approve_replacement(
+    path: &Path,
+    content: &str,
+    replacement: &Replacement,
+) -> Result<Approval> {
+    let (range, old, new) = (
+        replacement.start..replacement.end,
+        &replacement.old,
+        &replacement.new,
+    );
+    ensure_interactive("`bump --interactive` needs a terminal")?;
+    const CONTEXT: usize = 2;
+
+    let line_start = content[..range.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[range.end..]
+        .find('\n')
+        .map_or(content.len(), |i| range.end + i);
+    let line_number = content[..range.start].matches('\n').count() + 1;
+    let lines: Vec<&str> = content.lines().collect();
+
+    println!(
+        "\n  {} {}:{}",
+        style("📄").dim(),
+        style(path.display()).cyan(),
+        line_number
+    );
+    let first = line_number.saturating_sub(CONTEXT + 1);
+    for (index, line) in lines.iter().enumerate().skip(first).take(CONTEXT) {
+        if index + 1 < line_number {
+            println!("  {:>5} │ {}", style(index + 1).dim(), line);
+        }
+    }
+    let current = &content[line_start..line_end];
+    let replaced = format!(
+        "{}{}{}",
+        &content[line_start..range.start],
+        new,
+        &content[range.end..line_end]
+    );
+    println!("  {:>5} │ {}", style("-").red(), style(current).red());
+    println!("  {:>5} │ {}", style("+").green(), style(replaced).green());
+    for (index, line) in lines.iter().enumerate().skip(line_number).take(CONTEXT) {
+        println!("  {:>5} │ {}", style(index + 1).dim(), line);
+    }
+
+    let items = [
+        "Accept",
+        "Skip",
+        "Skip the rest of this file",
+        "Exclude this file from the location",
+    ];
+    let selection = Select::with_theme(&ColorfulTheme::default())
+        .with_prompt(format!("Replace `{old}` with `{new}`?"))
+        .default(0)
+        .items(items)
+        .interact()?;
+
+    match selection {
+        0 => Ok(Approval::Accept),
+        1 => Ok(Approval::Skip),
+        2 => Ok(Approval::SkipFile),
+        3 => Ok(Approval::Exclude),
+        _ => Err(anyhow!("Invalid selection")),
+    }
+}
+
+/// Ask if the excludes chosen during an interactive bump should be saved
+pub fn confirm_persist_excludes(excluded: &[(String, std::path::PathBuf)]) -> Result<bool> {
+    ensure_interactive("`bump --interactive` needs a terminal")?;
+    println!("\n🚫 Excluded during review:\n");
+    for (location, path) in excluded {
+        println!("  {} {} ({})", style("→").dim(), path.display(), location);
+    }
+    Ok(dialoguer::Confirm::new()
+        .with_prompt("Save these excludes to the configuration file?")
+        .default(false)
+        .interact()?)
+}
+
+/// Ask if the suggested locations should be added to the configuration file
+pub fn confirm_persist_locations() -> Result<bool> {
+    ensure_interactive("add the suggested locations to the configuration file manually")?;
+    Ok(dialoguer::Confirm::new()
+        .with_prompt("Add the suggested locations to the configuration file?")
+        .default(false)
+        .interact()?)
+}
+
+/// Print the ecosystems detected in the project and their version counts
+pub fn print_detected(detected: &[(String, usize)]) {
+    if detected.is_empty() {
+        println!("⚠️  No ecosystems detected");
+        return;
+    }
+    println!("\n🔍 Detected ecosystems:\n");
+    for (ecosystem, count) in detected {
+        println!("  ✓ {:<16} {} versions", ecosystem, count);
+    }
+    println!();
+}
+
 fn prompt_version() -> Result<semver::Version> {
+    ensure_interactive(
+        "no current version, set `version` in the configuration or use `bump --set-version`",
+    )?;
     let version = dialoguer::Input::<String>::new()
         .with_prompt("Enter Version")
         .default("0.1.0".to_string())
@@ -224,14 +439,22 @@ fn prompt_version() -> Result<semver::Version> {
     semver::Version::parse(&version).map_err(|e| anyhow!("Invalid version: {}", e))
 }
 
-/// Prompt for a new version based on the current version and bump mode
-pub fn new_version(config: &Config, bump_mode: &BumpMode) -> Result<semver::Version> {
-    let mut version = if let Some(version) = &config.version {
+/// Prompt for a new version based on the current version (of the stream) and bump mode,
+/// a specific version is used as is
+pub fn new_version(
+    config: &Config,
+    stream: Option<&str>,
+    bump_mode: &BumpMode,
+) -> Result<semver::Version> {
+    if let BumpMode::Version(version) = bump_mode {
+        return semver::Version::parse(version).context(format!("Invalid version: {version}"));
+    }
+    let mut version = if let Some(version) = config.stream_version(stream)? {
         semver::Version::parse(version).context(format!("Failed to parse version: {version}"))?
     } else {
         prompt_version()?
     };
-    update_version(&mut version, bump_mode);
+    update_version(&mut version, bump_mode)?;
     Ok(version)
 }
 
@@ -7,16 +7,32 @@ use console::style;
 use defaults::Defaults;
 use log::{debug, warn};
 
+mod audit;
+mod bump;
 mod cli;
 mod config;
 mod defaults;
+mod dependencies;
 mod error;
+mod github;
+mod hooks;
 mod interactive;
+mod plugins;
+mod remote;
+mod reports;
+mod rollback;
+mod scripts;
+mod tui;
 mod workflows;
+mod yaml;
 
 use crate::cli::*;
 use crate::config::*;
+use crate::error::Error;
+use crate::github::ActionReport;
 use crate::interactive::*;
+use crate::remote::Remote;
+use crate::reports::LocationCheck;
 use crate::workflows::*;
 
 /// Detect current version from Cargo.toml
@@ -40,108 +56,213 @@ fn detect_current_version(root: &std::path::Path) -> Result<semver::Version> {
     )
 }
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let arguments = init();
-    debug!("Finished initialising, starting main workflow...");
-
-    // Load Configuration
-    let defaults = Defaults::load()?;
-    debug!("Defaults Count - {}", defaults.locations.len());
-
-    let mut config = match Config::load(&arguments.root, &arguments.config) {
-        Ok(config) => config,
-        Err(e) => {
-            warn!("Failed to load configuration");
-            debug!("Error: {}", e);
-            Config::default()
-        }
-    };
-
-    // Subcommands
-    let mode = match &arguments.commands {
-        Some(ArgumentCommands::Init {
-            name,
-            version,
-            language_ecosystems,
-            defaults,
-        }) => {
-            debug!("Init Mode");
-
-            WorkflowMode::Init {
-                name: name.clone(),
-                version: version.clone(),
-                repository: None,
-                language_ecosystems: language_ecosystems.clone(),
-                enable_defaults: *defaults,
-            }
-        }
-        Some(ArgumentCommands::Sync) => {
+/// Workflow mode of a (non-init) subcommand for a project root or package
+fn command_mode(
+    arguments: &Arguments,
+    root: &std::path::Path,
+    config: &Config,
+    stream: Option<&str>,
+) -> Result<WorkflowMode> {
+    match &arguments.commands {
+        Some(ArgumentCommands::Sync { .. }) => {
             debug!("Sync Mode");
             // For sync, detect the actual version from Cargo.toml at runtime
-            let version = detect_current_version(&arguments.root)?;
-            WorkflowMode::Bump {
+            let version = detect_current_version(root)?;
+            Ok(WorkflowMode::Bump {
                 mode: BumpMode::Version(version.to_string()),
                 version,
-            }
+                counter: config.counter,
+            })
         }
         Some(ArgumentCommands::Bump {
             set_version,
             mode,
-            patch: _,
+            patch,
             minor,
             major,
+            ..
         }) => {
             debug!("Bump Mode");
 
-            let bump_mode = if !set_version.is_empty() {
+            // Flags are mutually exclusive (enforced by the CLI)
+            let bump_mode = if let Some(set_version) = set_version {
                 debug!("Manually setting version: {}", set_version);
-                BumpMode::Version(set_version.clone())
+                BumpMode::Version(set_version.to_string())
             } else if let Some(mode) = mode {
-                debug!("Setting mode: {} (dynamic)", mode);
-                BumpMode::from(mode)
-            } else if let Some(ref version) = config.version {
+                debug!("Setting mode: {:?}", mode);
+                mode.clone()
+            } else if *major {
+                BumpMode::Major
+            } else if *minor {
+                BumpMode::Minor
+            } else if *patch {
+                BumpMode::Patch
+            } else if let Some(version) = config.stream_version(stream)? {
                 debug!("Setting mode: Version (from config)");
                 BumpMode::Version(version.clone())
             } else {
-                if *minor {
-                    BumpMode::Minor
-                } else if *major {
-                    BumpMode::Major
-                } else {
-                    BumpMode::Patch
-                }
+                BumpMode::Patch
             };
             debug!("CLI Mode: {:?}", bump_mode);
 
-            let version = new_version(&config, &bump_mode)?;
+            let version = new_version(config, stream, &bump_mode)?;
 
-            WorkflowMode::Bump {
+            Ok(WorkflowMode::Bump {
                 mode: bump_mode,
                 version,
-            }
+                // Only bumps of the default version store the counter
+                counter: match stream {
+                    Some(_) => config.counter,
+                    None => config.counter.map(|c| c + 1),
+                },
+            })
+        }
+        Some(ArgumentCommands::Check { .. }) => Ok(WorkflowMode::Check),
+        Some(ArgumentCommands::Status { .. }) => Ok(WorkflowMode::Status),
+        Some(ArgumentCommands::Audit) => Ok(WorkflowMode::Audit),
+        _ => Ok(WorkflowMode::Display),
+    }
+}
+
+/// Load the configuration, the default configuration is only used when the
+/// configuration file does not exist
+fn load_config(arguments: &Arguments, defaults: &Defaults) -> Result<Config> {
+    let path = arguments.root.join(&arguments.config);
+    if !path.exists() {
+        if !matches!(arguments.commands, Some(ArgumentCommands::Init { .. })) {
+            warn!("Configuration file not found: {}", path.display());
         }
-        Some(ArgumentCommands::Display) => WorkflowMode::Display,
-        None => select_mode(&config)?,
+        return Ok(Config::default());
+    }
+    Config::load(
+        &arguments.root,
+        &arguments.config,
+        &arguments.variables,
+        defaults,
+    )
+    .map_err(|e| anyhow::anyhow!("Failed to load configuration: {}", e))
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let arguments = init();
+    set_non_interactive(arguments.non_interactive);
+    debug!("Finished initialising, starting main workflow...");
+
+    // Load Configuration
+    let defaults = Defaults::load(&arguments.defaults)?;
+    debug!("Defaults Count - {}", defaults.locations.len());
+
+    let mut config = load_config(&arguments, &defaults)?;
+
+    // Version stream and package to use
+    let (stream, package) = match &arguments.commands {
+        Some(ArgumentCommands::Bump {
+            stream, package, ..
+        })
+        | Some(ArgumentCommands::Display { stream, package })
+        | Some(ArgumentCommands::Check {
+            stream, package, ..
+        })
+        | Some(ArgumentCommands::Status { stream, package }) => (stream.clone(), package.clone()),
+        Some(ArgumentCommands::Sync { package }) => (None, package.clone()),
+        _ => (None, None),
+    };
+
+    // Project root and / or packages to run on
+    let mut targets = match &arguments.commands {
+        Some(_) => config.targets(&arguments.root, package.as_deref())?,
+        None => config
+            .targets(&arguments.root, None)?
+            .into_iter()
+            .take(1)
+            .collect(),
     };
+    if let Some(stream) = &stream {
+        targets.retain(|target| target.config.stream_version(Some(stream)).is_ok());
+        if targets.is_empty() {
+            anyhow::bail!("Unknown version stream: {}", stream);
+        }
+    }
+    let multiple = targets.len() > 1 || package.is_some();
+
+    // Subcommands
+    // Bumps selected in the interactive mode can be reviewed in the TUI
+    let mut review = false;
+    let mode = match &arguments.commands {
+        Some(ArgumentCommands::Init {
+            name,
+            version,
+            language_ecosystems,
+            detect,
+            defaults: enable_defaults,
+        }) => {
+            debug!("Init Mode");
+
+            let mut language_ecosystems = language_ecosystems.clone();
+            if *detect {
+                let detected = defaults.detect(&arguments.root)?;
+                print_detected(&detected);
+                for (ecosystem, _) in detected {
+                    if !language_ecosystems.contains(&ecosystem) {
+                        language_ecosystems.push(ecosystem);
+                    }
+                }
+            }
+
+            let version = match version {
+                Some(version) => Some(version.clone()),
+                None => detect_initial_version(
+                    &arguments.root,
+                    &config,
+                    &language_ecosystems,
+                    &defaults,
+                )?
+                .map(|v| v.to_string()),
+            };
 
-    let workflow = Workflow::init()
-        .root(arguments.root.clone())?
-        .mode(mode.clone())
-        .locations(config.locations.clone())?
-        .build();
+            WorkflowMode::Init {
+                name: name.clone(),
+                version,
+                repository: None,
+                host: None,
+                language_ecosystems,
+                enable_defaults: *enable_defaults,
+            }
+        }
+        Some(_) => match targets.first() {
+            Some(target) => {
+                command_mode(&arguments, &target.root, &target.config, stream.as_deref())?
+            }
+            None => WorkflowMode::Display,
+        },
+        None => {
+            let (mode, tui) = select_mode(&config, &arguments.root, &defaults)?;
+            review = tui;
+            mode
+        }
+    };
 
     match mode {
         WorkflowMode::Init {
             name,
             version,
             repository,
+            host,
             language_ecosystems,
             enable_defaults,
         } => {
+            // Detect the repository from the Git remote if not set
+            let remote = match (&repository, &config.repository) {
+                (None, None) => Remote::detect(&arguments.root),
+                _ => None,
+            };
             config.name = name.clone();
             config.version = version.clone();
-            config.repository = repository.clone();
+            config.repository = repository
+                .or(remote.as_ref().map(|r| r.repository.clone()))
+                .or(config.repository.take());
+            config.host = host.or(remote.map(|r| r.host)).or(config.host.take());
             config.default = enable_defaults;
 
             // Inline defaults
@@ -178,42 +299,374 @@ async fn main() -> Result<()> {
             );
             println!();
         }
-        WorkflowMode::Display => {
-            println!();
-            let version_text = config
-                .version
-                .as_ref()
-                .map(|v| format!("{}", style(v).green().bold()))
-                .unwrap_or_else(|| style("Not set").yellow().to_string());
+        WorkflowMode::Display | WorkflowMode::Status => {
+            let mut summaries = Vec::new();
+            for target in &targets {
+                if let Some(package) = &target.package {
+                    println!(
+                        "\n{} Package: {}",
+                        style("📦").cyan(),
+                        style(package).bold()
+                    );
+                }
+                println!();
 
-            println!("{} Current version: {}", style("ℹ").blue(), version_text);
-            println!("{}", style("─".repeat(60)).dim());
-            println!();
+                let mut summary = Summary::default();
+                for (name, version) in target.config.streams() {
+                    if stream.is_some() && name != stream {
+                        continue;
+                    }
+                    let locations = target.config.stream_locations(name.as_deref());
+                    if name.is_some() && locations.is_empty() {
+                        continue;
+                    }
 
-            workflow.display()?;
+                    let version_text = version
+                        .as_ref()
+                        .map(|v| format!("{}", style(v).green().bold()))
+                        .unwrap_or_else(|| style("Not set").yellow().to_string());
+
+                    match &name {
+                        Some(name) => println!(
+                            "{} Stream {}: {}",
+                            style("ℹ").blue(),
+                            style(name).cyan().bold(),
+                            version_text
+                        ),
+                        None => {
+                            println!("{} Current version: {}", style("ℹ").blue(), version_text)
+                        }
+                    }
+                    println!("{}", style("─".repeat(60)).dim());
+                    println!();
+
+                    let workflow = Workflow::init()
+                        .root(target.root.clone())?
+                        .mode(WorkflowMode::Display)
+                        .date(arguments.date)
+                        .current(version.clone())
+                        .locations(locations)?
+                        .build();
+                    let stream_summary = match mode {
+                        WorkflowMode::Status => workflow.status(version.as_deref())?,
+                        _ => workflow.display()?,
+                    };
+                    summary.files.extend(stream_summary.files);
+                    summary.matches += stream_summary.matches;
+                    println!();
+                }
+                summaries.push((target, target.config.version.clone(), summary));
+            }
+
+            if multiple {
+                print_packages_summary(&summaries);
+            }
+
+            if matches!(mode, WorkflowMode::Status) {
+                return Ok(());
+            }
+
+            if github::is_actions() {
+                let version = targets
+                    .first()
+                    .and_then(|t| t.config.stream_version(stream.as_deref()).ok().flatten())
+                    .cloned();
+                ActionReport {
+                    command: "display".to_string(),
+                    old_version: version.clone(),
+                    new_version: version,
+                    ..Default::default()
+                }
+                .write(&arguments.root)?;
+            }
 
-            println!();
             println!("{}", style("Note:").bold());
             println!("  This is a dry-run. No files were modified.");
             println!("  Run 'patch-release-me bump' to apply changes.");
             println!();
         }
-        WorkflowMode::Bump { mode, .. } => {
-            println!("\n{} Bumping version: {:?}", style("→").cyan(), mode);
-            println!("{}", style("─".repeat(60)).dim());
+        WorkflowMode::Audit => {
+            let root = arguments.root.canonicalize()?;
+            let mut versions = Vec::new();
+            let mut excludes = Vec::new();
+            let mut covered = Vec::new();
+            for target in &targets {
+                for (_, version) in target.config.streams() {
+                    if let Some(version) = version {
+                        versions.push(semver::Version::parse(&version)?);
+                    }
+                }
+                excludes.extend(target.config.excludes.clone());
+                covered.extend(
+                    Workflow::init()
+                        .root(target.root.clone())?
+                        .current(target.config.version.clone())
+                        .locations(target.config.locations.clone())?
+                        .build()
+                        .covered()?,
+                );
+            }
+            if versions.is_empty() {
+                anyhow::bail!("No version set in the configuration, run 'patch-release-me init'");
+            }
 
-            workflow.patch().await?;
+            let config_name = arguments.config.file_name();
+            let mut uncovered = audit::find_uncovered(&root, &versions, &excludes, &covered)?;
+            uncovered.retain(|item| item.path.file_name() != config_name);
 
             println!();
-            println!("{}", style("━".repeat(60)).dim());
+            if uncovered.is_empty() {
+                println!(
+                    "{} All versions are covered by a location",
+                    style("✓").green().bold()
+                );
+                println!();
+                return Ok(());
+            }
+
+            println!("{} Uncovered versions", style("🔍").cyan());
+            println!("{}", style("─".repeat(60)).dim());
+            for item in &uncovered {
+                let path = item.path.strip_prefix(&root).unwrap_or(&item.path);
+                println!(
+                    "  {} {}:{}  {}",
+                    style("✗").red(),
+                    style(path.display()).bold(),
+                    item.line,
+                    style(&item.value).red()
+                );
+            }
+            let locations = audit::suggest_locations(&root, &uncovered);
+            println!("{}", style("─".repeat(60)).dim());
             println!(
-                "{} Version bump completed successfully!",
-                style("✓").green().bold()
+                "  {} uncovered versions in {} files",
+                style(uncovered.len()).cyan().bold(),
+                style(locations.len()).cyan().bold()
             );
-            println!("{}", style("━".repeat(60)).dim());
+
+            let mut suggestion = std::collections::BTreeMap::new();
+            suggestion.insert("locations", &locations);
+            println!("\n{}\n", style("Suggested locations:").bold());
+            println!("{}", serde_yaml::to_string(&suggestion)?);
+
+            let config_path = arguments.root.join(&arguments.config);
+            if is_interactive() && config_path.exists() && confirm_persist_locations()? {
+                Config::persist_locations(&config_path, &locations)?;
+                println!(
+                    "{} Locations saved to {}",
+                    style("✓").green(),
+                    style(config_path.display()).cyan()
+                );
+            }
+        }
+        WorkflowMode::Check => {
+            let mut drift = Vec::new();
+            let mut checks: Vec<LocationCheck> = Vec::new();
+            let mut checked: Option<String> = None;
+            let mut failures = 0;
+            for target in &targets {
+                if let Some(package) = &target.package {
+                    println!(
+                        "\n{} Package: {}",
+                        style("📦").cyan(),
+                        style(package).bold()
+                    );
+                }
+
+                for (name, version) in target.config.streams() {
+                    if stream.is_some() && name != stream {
+                        continue;
+                    }
+                    let locations = target.config.stream_locations(name.as_deref());
+                    if name.is_some() && locations.is_empty() {
+                        continue;
+                    }
+                    let Some(version) = version else {
+                        warn!(
+                            "No version set for {}, skipping check",
+                            name.as_deref().unwrap_or("the project")
+                        );
+                        continue;
+                    };
+                    checked.get_or_insert(version.clone());
+
+                    let workflow = Workflow::init()
+                        .root(target.root.clone())?
+                        .mode(WorkflowMode::Bump {
+                            mode: BumpMode::Version(version.clone()),
+                            version: semver::Version::parse(&version)?,
+                            counter: target.config.counter,
+                        })
+                        .date(arguments.date)
+                        .current(Some(version.clone()))
+                        .locations(locations)?
+                        .build();
+                    let found = workflow.drift()?;
+                    let violations = workflow.violations()?;
+                    for violation in &violations {
+                        println!("{} {}", style("✗").red(), violation);
+                    }
+                    failures += violations.len();
+
+                    let project = target
+                        .package
+                        .as_deref()
+                        .or(target.config.name.as_deref())
+                        .unwrap_or("project");
+                    let suite = match &name {
+                        Some(name) => format!("{project} ({name})"),
+                        None => project.to_string(),
+                    };
+                    for location in workflow.locations() {
+                        if (location.regexes.is_empty() && !location.r#type.is_plugin())
+                            || !matches!(
+                                location.r#type,
+                                LocationType::Version
+                                    | LocationType::Counter
+                                    | LocationType::Plugin
+                            )
+                        {
+                            continue;
+                        }
+                        let violation = violations.iter().find(|v| {
+                            matches!(v, Error::UnexpectedMatchCount(name, ..) if name == &location.name)
+                        });
+                        checks.push(LocationCheck {
+                            suite: suite.clone(),
+                            location: location.name.clone(),
+                            version: version.clone(),
+                            violation: violation.map(|v| v.to_string()),
+                            drift: found
+                                .iter()
+                                .filter(|d| d.location == location.name)
+                                .cloned()
+                                .collect(),
+                        });
+                    }
+
+                    let label = match &name {
+                        Some(name) => format!("{} ({})", style(&version).green().bold(), name),
+                        None => style(&version).green().bold().to_string(),
+                    };
+                    if found.is_empty() {
+                        println!("{} All locations match {}", style("✓").green(), label);
+                    } else {
+                        println!("{} Locations not matching {}", style("✗").red(), label);
+                    }
+                    for item in &found {
+                        let path = item
+                            .path
+                            .strip_prefix(workflow.root())
+                            .unwrap_or(&item.path);
+                        println!(
+                            "  {} {}:{} ({}) {} {} {}",
+                            style("✗").red(),
+                            style(path.display()).bold(),
+                            item.line,
+                            style(&item.location).cyan(),
+                            style(&item.found).red(),
+                            style("→").dim(),
+                            style(&item.expected).green()
+                        );
+                    }
+                    drift.extend(found);
+                }
+            }
             println!();
+
+            if github::is_actions() {
+                ActionReport {
+                    command: "check".to_string(),
+                    old_version: checked.clone(),
+                    new_version: checked,
+                    drift: Some(drift.clone()),
+                    ..Default::default()
+                }
+                .write(&arguments.root)?;
+            }
+            if let Some(ArgumentCommands::Check { sarif, junit, .. }) = &arguments.commands {
+                let root = arguments.root.canonicalize()?;
+                if let Some(path) = sarif {
+                    std::fs::write(path, reports::sarif(&root, &checks)?)?;
+                    println!(
+                        "{} SARIF report written to {}",
+                        style("✓").green(),
+                        style(path.display()).cyan()
+                    );
+                }
+                if let Some(path) = junit {
+                    std::fs::write(path, reports::junit(&root, &checks))?;
+                    println!(
+                        "{} JUnit report written to {}",
+                        style("✓").green(),
+                        style(path.display()).cyan()
+                    );
+                }
+            }
+            if failures > 0 {
+                anyhow::bail!(
+                    "{} locations matched more or less often than expected",
+                    failures
+                );
+            }
+            if !drift.is_empty() {
+                anyhow::bail!(
+                    "{} values do not match the version, run 'patch-release-me sync' or 'patch-release-me bump' to update them",
+                    drift.len()
+                );
+            }
+        }
+        WorkflowMode::Bump { .. } => {
+            bump::run(
+                &arguments,
+                &config,
+                &targets,
+                stream.as_deref(),
+                &mode,
+                review,
+                multiple,
+            )
+            .await?;
         }
     }
 
     Ok(())
 }
+
+/// Print a summary table of every package that was processed
+fn print_packages_summary(summaries: &[(&Target, Option<String>, Summary)]) {
+    println!("{}", style("Packages").bold());
+    for (target, version, summary) in summaries {
+        println!(
+            "  {:<24} {:<12} {} files, {} matches",
+            style(target.package.as_deref().unwrap_or("(root)")).cyan(),
+            style(version.as_deref().unwrap_or("-")).green(),
+            summary.files.len(),
+            summary.matches
+        );
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_load_config() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().to_str().unwrap();
+        let arguments = Arguments::parse_from(["patch-release-me", "--root", root, "bump"]);
+        let defaults = Defaults::load_from(&[]).unwrap();
+        assert!(load_config(&arguments, &defaults).is_ok());
+
+        std::fs::write(
+            directory.path().join(".release.yml"),
+            "version: 1.2.3\ndefault: false\nlocations:\n  - name: Docs\n    paths: [README.md]\n    patterns: ['v([0-9.]+)']\n    script: 'version.'\n",
+        )
+        .unwrap();
+        let error = load_config(&arguments, &defaults).unwrap_err().to_string();
+        assert!(error.contains("Location `Docs`: Invalid script"), "{error}");
+    }
+}
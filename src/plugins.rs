@@ -0,0 +1,274 @@
+//! External patchers for files no pattern can express.
+//!
+//! A `plugin` location runs its command for every file it matches. The command
+//! receives a JSON request on stdin:
+//!
+//! ```json
+//! {
+//!   "path": "proto/service.desc",
+//!   "contents": "...",
+//!   "contents_base64": "...",
+//!   "old_version": "1.2.3",
+//!   "new_version": "1.3.0",
+//!   "location": { "name": "Descriptor", "type": "plugin", "plugin": "..." }
+//! }
+//! ```
+//!
+//! `contents` is `null` for files which are not valid UTF-8, `contents_base64`
+//! is always set. The plugin prints a JSON list of edits (byte offsets into
+//! the file) on stdout:
+//!
+//! ```json
+//! [{ "start": 120, "end": 125, "new": "1.3.0" }]
+//! ```
+//!
+//! Edits become replacements like the captures of a pattern, so they are
+//! previewed, reviewed, checked and rolled back the same way. A plugin which
+//! runs longer than the `timeout` of its location is stopped like a hook. The
+//! timeout of the hooks is copied to plugin locations without a `timeout`
+//! when the configuration is resolved.
+use anyhow::{Result, anyhow};
+use base64::Engine;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+
+use crate::config::LocationPattern;
+use crate::hooks::{DEFAULT_TIMEOUT, shell, spawn, wait};
+use crate::workflows::Replacement;
+
+/// Request written to the stdin of a plugin
+#[derive(Debug, Serialize)]
+struct PluginRequest<'a> {
+    /// Path of the file relative to the project root
+    path: String,
+    /// Contents of the file (`None` if it is not valid UTF-8)
+    contents: Option<&'a str>,
+    /// Contents of the file encoded with base64
+    contents_base64: String,
+    /// Current version of the project (if known)
+    old_version: Option<&'a str>,
+    /// Version to write
+    new_version: String,
+    /// Configuration of the location
+    location: &'a LocationPattern,
+}
+
+/// Edit returned by a plugin
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct Edit {
+    /// Start offset (in bytes) in the file
+    pub start: usize,
+    /// End offset (in bytes) in the file
+    pub end: usize,
+    /// New value
+    pub new: String,
+}
+
+/// Run the plugin of a location on a file and return its edits as replacements
+pub fn replacements(
+    root: &Path,
+    path: &Path,
+    contents: &[u8],
+    old_version: Option<&str>,
+    new_version: &semver::Version,
+    location: &LocationPattern,
+) -> Result<Vec<Replacement>> {
+    let command = location.plugin.as_deref().ok_or_else(|| {
+        anyhow!(
+            "Location `{}` is a plugin location but `plugin` is not set",
+            location.name
+        )
+    })?;
+    let request = serde_json::to_vec(&PluginRequest {
+        path: path
+            .strip_prefix(root)
+            .unwrap_or(path)
+            .display()
+            .to_string()
+            .replace('\\', "/"),
+        contents: std::str::from_utf8(contents).ok(),
+        contents_base64: base64::engine::general_purpose::STANDARD.encode(contents),
+        old_version,
+        new_version: new_version.to_string(),
+        location,
+    })?;
+
+    debug!("Running plugin `{}` for {:?}", command, path);
+    let mut child = spawn(
+        shell(command)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit()),
+    )
+    .map_err(|e| anyhow!("Failed to run plugin `{}`: {}", command, e))?;
+
+    // Written and read from threads so a plugin printing before reading all
+    // of its input does not block
+    let mut stdin = child.stdin.take().expect("stdin is piped");
+    let writer = std::thread::spawn(move || stdin.write_all(&request));
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let reader = std::thread::spawn(move || {
+        let mut output = Vec::new();
+        stdout.read_to_end(&mut output).map(|_| output)
+    });
+
+    // `Config::resolve` already fell back to the timeout of the hooks
+    let timeout = Duration::from_secs(location.timeout.unwrap_or(DEFAULT_TIMEOUT));
+    let status = wait(&mut child, timeout);
+    // A plugin which does not read its input closes the pipe early
+    let _ = writer.join();
+    let output = reader
+        .join()
+        .map_err(|_| anyhow!("Failed to read the output of plugin `{}`", command))?;
+    let status =
+        status.map_err(|e| anyhow!("Plugin `{}` failed for {}: {}", command, path.display(), e))?;
+
+    if !status.success() {
+        return Err(anyhow!(
+            "Plugin `{}` failed for {}: exited with {}",
+            command,
+            path.display(),
+            status
+        ));
+    }
+    let edits: Vec<Edit> = serde_json::from_slice(&output?).map_err(|e| {
+        anyhow!(
+            "Plugin `{}` returned invalid edits for {}: {}",
+            command,
+            path.display(),
+            e
+        )
+    })?;
+    to_replacements(contents, edits)
+        .map_err(|e| anyhow!("Plugin `{}` edits for {}: {}", command, path.display(), e))
+}
+
+/// Validate edits against the contents of a file and sort them by offset
+fn to_replacements(contents: &[u8], mut edits: Vec<Edit>) -> Result<Vec<Replacement>> {
+    edits.sort_by_key(|edit| edit.start);
+    let text = std::str::from_utf8(contents).ok();
+
+    let mut replacements: Vec<Replacement> = Vec::new();
+    for edit in edits {
+        if edit.start > edit.end || edit.end > contents.len() {
+            return Err(anyhow!(
+                "edit {}..{} is out of bounds ({} bytes)",
+                edit.start,
+                edit.end,
+                contents.len()
+            ));
+        }
+        // Edits of a text file have to keep it valid UTF-8
+        if text.is_some_and(|text| {
+            !text.is_char_boundary(edit.start) || !text.is_char_boundary(edit.end)
+        }) {
+            return Err(anyhow!(
+                "edit {}..{} splits a character",
+                edit.start,
+                edit.end
+            ));
+        }
+        let replacement = Replacement {
+            start: edit.start,
+            end: edit.end,
+            old: String::from_utf8_lossy(&contents[edit.start..edit.end]).into_owned(),
+            new: edit.new,
+        };
+        if replacements.iter().any(|r| r.overlaps(&replacement)) {
+            return Err(anyhow!(
+                "edit {}..{} overlaps another edit",
+                edit.start,
+                edit.end
+            ));
+        }
+        replacements.push(replacement);
+    }
+    Ok(replacements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(start: usize, end: usize, new: &str) -> Edit {
+        Edit {
+            start,
+            end,
+            new: new.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_to_replacements() {
+        let contents = "name = \"é\"\nversion = \"1.2.3\"\n".as_bytes();
+        let replacements =
+            to_replacements(contents, vec![edit(23, 28, "1.3.0"), edit(0, 4, "title")]).unwrap();
+        assert_eq!(replacements[0].old, "name");
+        assert_eq!(replacements[1].old, "1.2.3");
+        assert_eq!(replacements[1].new, "1.3.0");
+
+        assert!(to_replacements(contents, vec![edit(23, 99, "1.3.0")]).is_err());
+        assert!(to_replacements(contents, vec![edit(9, 10, "e")]).is_err());
+        assert!(to_replacements(contents, vec![edit(23, 28, "1"), edit(25, 26, "2")]).is_err());
+
+        // Any byte range of a binary file can be edited
+        let binary = b"\xff\xfe\x00v1.2.3\x00";
+        let replacements = to_replacements(binary, vec![edit(4, 9, "1.3.0")]).unwrap();
+        assert_eq!(replacements[0].old, "1.2.3");
+        assert!(to_replacements(binary, vec![edit(0, 1, "")]).is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_replacements() {
+        let root = std::env::temp_dir();
+        let location = LocationPattern {
+            name: "Descriptor".to_string(),
+            r#type: crate::config::LocationType::Plugin,
+            plugin: Some(
+                r#"grep '"new_version":"1.3.0"' | grep -q '"contents_base64":"dmVyc2lvbiAxLjIuMw=="' && echo '[{"start": 8, "end": 13, "new": "1.3.0"}]'"#
+                    .to_string(),
+            ),
+            ..Default::default()
+        };
+        let replacements = replacements(
+            &root,
+            &root.join("service.desc"),
+            b"version 1.2.3",
+            Some("1.2.3"),
+            &semver::Version::new(1, 3, 0),
+            &location,
+        )
+        .unwrap();
+        assert_eq!(replacements.len(), 1);
+        assert_eq!(replacements[0].old, "1.2.3");
+
+        let failing = LocationPattern {
+            plugin: Some("exit 1".to_string()),
+            ..location.clone()
+        };
+        let hanging = LocationPattern {
+            plugin: Some("sleep 30".to_string()),
+            timeout: Some(1),
+            ..location
+        };
+        for location in [failing, hanging] {
+            assert!(
+                super::replacements(
+                    &root,
+                    &root.join("service.desc"),
+                    b"version 1.2.3",
+                    None,
+                    &semver::Version::new(1, 3, 0),
+                    &location,
+                )
+                .is_err()
+            );
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Git remote URL parsing for detecting the repository and host of a project.
+//!
+//! Supports HTTPS, SSH (`ssh://`) and scp-style (`git@host:owner/name`) URLs
+//! for any forge, including nested GitLab groups and custom hosts / ports.
+use log::debug;
+use std::path::Path;
+
+/// Repository hosted on a Git forge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remote {
+    /// Host of the forge (`github.com`, `gitlab.example.com`)
+    pub host: String,
+    /// Repository path (`owner/name` or `group/subgroup/name`)
+    pub repository: String,
+}
+
+impl Remote {
+    /// Parse a Git remote URL
+    pub fn parse(url: &str) -> Option<Self> {
+        let url = url.trim();
+
+        let (host, path) = if let Some((_, rest)) = url.split_once("://") {
+            // `https://host/path`, `ssh://user@host:port/path`, `git://host/path`
+            let (authority, path) = rest.split_once('/')?;
+            let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+            let host = host.split_once(':').map_or(host, |(h, _)| h);
+            (host, path)
+        } else {
+            // scp-style `user@host:path`
+            let (authority, path) = url.split_once(':')?;
+            if authority.contains('/') {
+                return None;
+            }
+            let host = authority.rsplit_once('@').map_or(authority, |(_, h)| h);
+            (host, path)
+        };
+
+        let repository = path
+            .trim_matches('/')
+            .trim_end_matches(".git")
+            .trim_end_matches('/')
+            .to_string();
+        if host.is_empty() || !repository.contains('/') {
+            return None;
+        }
+
+        Some(Self {
+            host: host.to_lowercase(),
+            repository,
+        })
+    }
+
+    /// Detect the `origin` remote of the Git repository at `root`
+    pub fn detect(root: &Path) -> Option<Self> {
+        let output = std::process::Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["remote", "get-url", "origin"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            debug!(
+                "No Git remote found: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+            return None;
+        }
+        Self::parse(&String::from_utf8_lossy(&output.stdout))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn remote(host: &str, repository: &str) -> Option<Remote> {
+        Some(Remote {
+            host: host.to_string(),
+            repository: repository.to_string(),
+        })
+    }
+
+    #[test]
+    fn test_parse_github() {
+        let expected = remote("github.com", "42ByteLabs/patch-release-me");
+        assert_eq!(
+            Remote::parse("https://github.com/42ByteLabs/patch-release-me.git"),
+            expected
+        );
+        assert_eq!(
+            Remote::parse("git@github.com:42ByteLabs/patch-release-me.git"),
+            expected
+        );
+        assert_eq!(
+            Remote::parse("https://token@github.com/42ByteLabs/patch-release-me/"),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_other_forges() {
+        assert_eq!(
+            Remote::parse("git@gitlab.com:group/sub/proj.git"),
+            remote("gitlab.com", "group/sub/proj")
+        );
+        assert_eq!(
+            Remote::parse("ssh://git@git.example.com:2222/team/api.git"),
+            remote("git.example.com", "team/api")
+        );
+        assert_eq!(
+            Remote::parse("https://bitbucket.org/acme/api"),
+            remote("bitbucket.org", "acme/api")
+        );
+        assert_eq!(Remote::parse("/srv/git/api.git"), None);
+        assert_eq!(Remote::parse(""), None);
+    }
+}
@@ -0,0 +1,242 @@
+//! Machine readable reports of the drift check.
+//!
+//! Drift can be exported as SARIF 2.1.0 (one result per value which does not
+//! match the version) for code scanning, and as JUnit XML (one test case per
+//! location) for test report tooling.
+use anyhow::Result;
+use std::path::Path;
+
+use crate::cli::VERSION_NUMBER;
+use crate::workflows::Drift;
+
+/// SARIF rule reported for drift
+const SARIF_RULE: &str = "version-drift";
+
+/// Result of checking a location against the version
+#[derive(Debug, Clone)]
+pub struct LocationCheck {
+    /// Project, package and / or stream the location belongs to
+    pub suite: String,
+    /// Name of the location
+    pub location: String,
+    /// Expected version
+    pub version: String,
+    /// Values which do not match the version
+    pub drift: Vec<Drift>,
+    /// Location matched more or less often than expected
+    pub violation: Option<String>,
+}
+
+impl LocationCheck {
+    /// If the location failed the check
+    pub fn failed(&self) -> bool {
+        !self.drift.is_empty() || self.violation.is_some()
+    }
+}
+
+/// SARIF 2.1.0 log of the drift
+pub fn sarif(root: &Path, checks: &[LocationCheck]) -> Result<String> {
+    let results: Vec<serde_json::Value> = checks
+        .iter()
+        .flat_map(|check| check.drift.iter())
+        .map(|drift| {
+            serde_json::json!({
+                "ruleId": SARIF_RULE,
+                "level": "error",
+                "message": {
+                    "text": format!(
+                        "{}: found `{}`, expected `{}`",
+                        drift.location, drift.found, drift.expected
+                    ),
+                },
+                "locations": [{
+                    "physicalLocation": {
+                        "artifactLocation": {
+                            "uri": relative(root, &drift.path),
+                            "uriBaseId": "%SRCROOT%",
+                        },
+                        "region": {
+                            "startLine": drift.line,
+                            "startColumn": drift.column,
+                            "endColumn": drift.column + drift.found.chars().count(),
+                        },
+                    },
+                }],
+            })
+        })
+        .collect();
+
+    let log = serde_json::json!({
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "patch-release-me",
+                    "version": VERSION_NUMBER,
+                    "informationUri": "https://github.com/42ByteLabs/patch-release-me",
+                    "rules": [{
+                        "id": SARIF_RULE,
+                        "name": "VersionDrift",
+                        "shortDescription": {
+                            "text": "Version does not match the configured version",
+                        },
+                        "defaultConfiguration": { "level": "error" },
+                    }],
+                },
+            },
+            "results": results,
+        }],
+    });
+    Ok(serde_json::to_string_pretty(&log)?)
+}
+
+/// JUnit XML report with a test suite per project / package and a test case
+/// per location
+pub fn junit(root: &Path, checks: &[LocationCheck]) -> String {
+    let failures = checks.iter().filter(|c| c.failed()).count();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"patch-release-me\" tests=\"{}\" failures=\"{}\">\n",
+        checks.len(),
+        failures
+    ));
+
+    let mut suites: Vec<&str> = Vec::new();
+    for check in checks {
+        if !suites.contains(&check.suite.as_str()) {
+            suites.push(&check.suite);
+        }
+    }
+    for suite in suites {
+        let cases: Vec<&LocationCheck> = checks.iter().filter(|c| c.suite == suite).collect();
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+            escape_xml(suite),
+            cases.len(),
+            cases.iter().filter(|c| c.failed()).count()
+        ));
+        for case in cases {
+            xml.push_str(&format!(
+                "    <testcase classname=\"{}\" name=\"{}\"",
+                escape_xml(suite),
+                escape_xml(&case.location)
+            ));
+            if !case.failed() {
+                xml.push_str(" />\n");
+                continue;
+            }
+            xml.push_str(">\n");
+            if let Some(violation) = &case.violation {
+                xml.push_str(&format!(
+                    "      <failure type=\"UnexpectedMatches\" message=\"{}\" />\n",
+                    escape_xml(violation)
+                ));
+            }
+            if case.drift.is_empty() {
+                xml.push_str("    </testcase>\n");
+                continue;
+            }
+            let details: Vec<String> = case
+                .drift
+                .iter()
+                .map(|drift| {
+                    format!(
+                        "{}:{}:{} found `{}`, expected `{}`",
+                        relative(root, &drift.path),
+                        drift.line,
+                        drift.column,
+                        drift.found,
+                        drift.expected
+                    )
+                })
+                .collect();
+            xml.push_str(&format!(
+                "      <failure type=\"VersionDrift\" message=\"{}\">{}</failure>\n",
+                escape_xml(&format!(
+                    "{} values do not match {}",
+                    case.drift.len(),
+                    case.version
+                )),
+                escape_xml(&details.join("\n"))
+            ));
+            xml.push_str("    </testcase>\n");
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// Path relative to the root with `/` separators, as shown in reports
+pub(crate) fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+        .replace('\\', "/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn checks() -> Vec<LocationCheck> {
+        vec![
+            LocationCheck {
+                suite: "demo".to_string(),
+                location: "Cargo".to_string(),
+                version: "1.3.0".to_string(),
+                drift: vec![Drift {
+                    location: "Cargo".to_string(),
+                    path: PathBuf::from("/repo/Cargo.toml"),
+                    line: 3,
+                    column: 12,
+                    found: "1.2.3".to_string(),
+                    expected: "1.3.0".to_string(),
+                }],
+                violation: None,
+            },
+            LocationCheck {
+                suite: "demo".to_string(),
+                location: "Docs <README>".to_string(),
+                version: "1.3.0".to_string(),
+                drift: vec![],
+                violation: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_sarif() {
+        let log: serde_json::Value =
+            serde_json::from_str(&sarif(Path::new("/repo"), &checks()).unwrap()).unwrap();
+        assert_eq!(log["version"], "2.1.0");
+        let results = log["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 1);
+        let location = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(location["artifactLocation"]["uri"], "Cargo.toml");
+        assert_eq!(location["region"]["startLine"], 3);
+        assert_eq!(location["region"]["endColumn"], 17);
+    }
+
+    #[test]
+    fn test_junit() {
+        let xml = junit(Path::new("/repo"), &checks());
+        assert!(xml.contains("<testsuites name=\"patch-release-me\" tests=\"2\" failures=\"1\">"));
+        assert!(xml.contains("<testcase classname=\"demo\" name=\"Docs &lt;README&gt;\" />"));
+        assert!(xml.contains(
+            "<failure type=\"VersionDrift\" message=\"1 values do not match 1.3.0\">Cargo.toml:3:12 found `1.2.3`, expected `1.3.0`</failure>"
+        ));
+    }
+}
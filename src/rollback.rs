@@ -0,0 +1,142 @@
+//! Journal of the files patched during a run.
+//!
+//! Every file is recorded with its original content before it is first
+//! written, so a failed bump (for example a failing hook) can restore the
+//! project to the state it was in before the run.
+use anyhow::Result;
+use console::style;
+use log::{debug, warn};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Original content of the files written during a bump
+#[derive(Debug, Default)]
+pub struct Journal {
+    entries: Mutex<Vec<(PathBuf, Vec<u8>)>>,
+}
+
+impl Journal {
+    /// Write a file, recording its original content the first time it is written
+    pub fn write(&self, path: &Path, content: impl AsRef<[u8]>) -> Result<()> {
+        {
+            let mut entries = self.entries.lock().unwrap();
+            if !entries.iter().any(|(p, _)| p == path) {
+                entries.push((path.to_path_buf(), std::fs::read(path)?));
+            }
+        }
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    /// Number of files written so far
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Files written since the journal had `start` entries
+    pub fn files_since(&self, start: usize) -> Vec<PathBuf> {
+        self.entries
+            .lock()
+            .unwrap()
+            .iter()
+            .skip(start)
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// Restore every written file to its original content, returns the number
+    /// of files restored
+    pub fn restore(&self) -> Result<usize> {
+        let mut entries = self.entries.lock().unwrap();
+        let count = entries.len();
+        for (path, content) in entries.drain(..).rev() {
+            debug!("Restoring {:?}", path);
+            std::fs::write(&path, content)?;
+        }
+        Ok(count)
+    }
+}
+
+/// Owns the journal of a bump and restores the written files when dropped
+/// before it is disarmed, so a bump which fails part way (or panics) does not
+/// leave the project half patched
+pub struct Guard {
+    journal: Journal,
+    armed: bool,
+}
+
+impl Guard {
+    /// Start guarding the files written from now on
+    pub fn new() -> Self {
+        Self {
+            journal: Journal::default(),
+            armed: true,
+        }
+    }
+
+    /// Journal to write the files of the bump with
+    pub fn journal(&self) -> &Journal {
+        &self.journal
+    }
+
+    /// Keep the written files
+    pub fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+        match self.journal.restore() {
+            Ok(0) => {}
+            Ok(restored) => println!(
+                "{} Rolled back {} patched files",
+                style("↺").yellow(),
+                style(restored).bold()
+            ),
+            Err(error) => warn!("Failed to roll back the patched files: {}", error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path().to_path_buf();
+        std::fs::write(&path, "version = 1.2.3").unwrap();
+
+        let journal = Journal::default();
+        journal.write(&path, "version = 1.3.0").unwrap();
+        let start = journal.len();
+        journal.write(&path, "version = 2.0.0").unwrap();
+        assert_eq!(journal.files_since(0), vec![path.clone()]);
+        assert!(journal.files_since(start).is_empty());
+
+        assert_eq!(journal.restore().unwrap(), 1);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "version = 1.2.3");
+    }
+
+    #[test]
+    fn test_guard() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let path = file.path().to_path_buf();
+        std::fs::write(&path, "version = 1.2.3").unwrap();
+
+        let guard = Guard::new();
+        guard.journal().write(&path, "version = 1.3.0").unwrap();
+        drop(guard);
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "version = 1.2.3");
+
+        let guard = Guard::new();
+        guard.journal().write(&path, "version = 1.3.0").unwrap();
+        guard.disarm();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "version = 1.3.0");
+    }
+}
@@ -0,0 +1,138 @@
+//! Scripted version transforms using Rhai.
+//!
+//! A location (or a single pattern) with a `script` renders the new value of
+//! every capture by running the script with the captured value (`capture`) and
+//! the new version (`version`, with `major`, `minor`, `patch`, `pre` and
+//! `build` properties). The value of the last expression, a string or a
+//! version, is the replacement. Scripts are compiled once when the
+//! configuration is loaded.
+//!
+//! Scripts are sandboxed: modules can not be imported (so nothing is read from
+//! the filesystem), `eval` is disabled, output is discarded and the number of
+//! operations is limited so a script can not hang a bump.
+use anyhow::{Result, anyhow};
+use rhai::{AST, Dynamic, Engine, Scope};
+
+/// Maximum number of operations a script can run
+const MAX_OPERATIONS: u64 = 100_000;
+/// Maximum length of a string in a script
+const MAX_STRING_SIZE: usize = 64 * 1024;
+/// Maximum number of items in an array or a map in a script
+const MAX_COLLECTION_SIZE: usize = 1024;
+
+/// Sandboxed engine with the `semver::Version` type registered
+fn engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_COLLECTION_SIZE)
+        .set_max_map_size(MAX_COLLECTION_SIZE)
+        .set_max_call_levels(32)
+        .set_max_expr_depths(64, 32)
+        .disable_symbol("eval")
+        .on_print(|_| {})
+        .on_debug(|_, _, _| {});
+
+    engine
+        .register_type_with_name::<semver::Version>("Version")
+        .register_get("major", |v: &mut semver::Version| v.major as i64)
+        .register_get("minor", |v: &mut semver::Version| v.minor as i64)
+        .register_get("patch", |v: &mut semver::Version| v.patch as i64)
+        .register_get("pre", |v: &mut semver::Version| v.pre.to_string())
+        .register_get("build", |v: &mut semver::Version| v.build.to_string())
+        .register_fn("to_string", |v: &mut semver::Version| v.to_string());
+    engine
+}
+
+/// Compiled script
+#[derive(Debug, Clone)]
+pub struct Script {
+    ast: AST,
+}
+
+impl Script {
+    /// Compile a script, reporting syntax errors and disabled features
+    pub fn compile(script: &str) -> Result<Self> {
+        let ast = engine()
+            .compile(script)
+            .map_err(|e| anyhow!("Invalid script: {}", e))?;
+        Ok(Self { ast })
+    }
+
+    /// Run the script with the captured value and the new version, returns
+    /// the replacement
+    pub fn render(&self, capture: &str, version: &semver::Version) -> Result<String> {
+        let mut scope = Scope::new();
+        scope.push_constant("capture", capture.to_string());
+        scope.push_constant("version", version.clone());
+
+        let result = engine()
+            .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast)
+            .map_err(|e| anyhow!("Script failed: {}", e))?;
+        if result.is::<semver::Version>() {
+            return Ok(result.cast::<semver::Version>().to_string());
+        }
+        let type_name = result.type_name();
+        result.into_string().map_err(|_| {
+            anyhow!(
+                "Script returned `{}`, expected a string or a version",
+                type_name
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(script: &str, capture: &str, version: &semver::Version) -> Result<String> {
+        Script::compile(script)?.render(capture, version)
+    }
+
+    #[test]
+    fn test_render() {
+        let version = semver::Version::parse("1.3.0").unwrap();
+        assert_eq!(
+            render(r#"`${version.major}.x`"#, "1.x", &version).unwrap(),
+            "1.x"
+        );
+        assert_eq!(
+            render(
+                r#"version.to_string() + "-alpine""#,
+                "1.2.3-alpine",
+                &version
+            )
+            .unwrap(),
+            "1.3.0-alpine"
+        );
+        assert_eq!(
+            render(
+                r#"if capture.starts_with("v") { "v" + version } else { version }"#,
+                "v1.2.3",
+                &version
+            )
+            .unwrap(),
+            "v1.3.0"
+        );
+        assert_eq!(render("version", "1.2.3", &version).unwrap(), "1.3.0");
+    }
+
+    #[test]
+    fn test_render_sandbox() {
+        let version = semver::Version::new(1, 3, 0);
+        assert!(render(r#"import "secrets" as s; s::value"#, "", &version).is_err());
+        assert!(render(r#"eval("1 + 1")"#, "", &version).is_err());
+        assert!(render("loop {}", "", &version).is_err());
+        assert!(render("let a = []; loop { a.push(1); }", "", &version).is_err());
+    }
+
+    #[test]
+    fn test_render_result() {
+        let version = semver::Version::new(1, 3, 0);
+        assert!(Script::compile("let x = ").is_err());
+        assert!(render("let x = 1;", "", &version).is_err());
+        assert!(render("42", "", &version).is_err());
+    }
+}
@@ -0,0 +1,415 @@
+//! Full-screen terminal UI for reviewing a bump before it is applied.
+//!
+//! Lists every location, the files it matched and each capture. Locations,
+//! files and matches can be toggled and the diff of the current selection is
+//! previewed before the approved changes are applied by the [`Workflow`].
+use anyhow::Result;
+use console::style;
+use ratatui::DefaultTerminal;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+
+use crate::interactive::ensure_interactive;
+use crate::rollback::Journal;
+use crate::workflows::{Change, Replacement, Summary, Workflow, text};
+
+/// Number of lines shown around a change in the preview
+const CONTEXT_LINES: usize = 2;
+
+/// Row in the list of locations, files and matches
+#[derive(Debug, Clone)]
+enum Row {
+    /// Location (name) and the indexes of its changes
+    Location(String, Vec<usize>),
+    /// File of a change
+    File(usize),
+    /// Replacement in a change
+    Match(usize, usize),
+}
+
+/// State of the review
+struct Review<'a> {
+    workflow: &'a Workflow,
+    changes: Vec<Change>,
+    contents: Vec<String>,
+    selected: Vec<Vec<bool>>,
+    rows: Vec<Row>,
+    state: ListState,
+    scroll: u16,
+}
+
+/// Review the changes of a bump in the TUI and apply the approved ones,
+/// returns `None` when the review is cancelled
+pub fn review(workflow: &Workflow, journal: &Journal) -> Result<Option<Summary>> {
+    ensure_interactive("the review UI needs a terminal, use `bump` without `--tui`")?;
+
+    let changes = workflow.plan()?;
+    if changes.is_empty() {
+        println!("  {} No changes found", style("ℹ").blue());
+        return Ok(Some(Summary::default()));
+    }
+
+    let mut review = Review::new(workflow, changes)?;
+    let mut terminal = ratatui::init();
+    let result = review.run(&mut terminal);
+    ratatui::restore();
+
+    if !result? {
+        println!(
+            "  {} Review cancelled, no files were modified",
+            style("ℹ").blue()
+        );
+        return Ok(None);
+    }
+
+    let summary = workflow.apply(&review.approved(), journal)?;
+    for path in &summary.files {
+        println!("  {} {}", style("📝").cyan(), style(path.display()).bold());
+    }
+    println!("{}", style("─".repeat(60)).dim());
+    println!(
+        "  {} files updated with {} changes",
+        style(summary.files.len()).cyan().bold(),
+        style(summary.matches).cyan().bold()
+    );
+    Ok(Some(summary))
+}
+
+impl<'a> Review<'a> {
+    fn new(workflow: &'a Workflow, changes: Vec<Change>) -> Result<Self> {
+        let contents = changes
+            .iter()
+            .map(|change| std::fs::read(&change.path).map(|content| text(&content).into_owned()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        let selected = changes
+            .iter()
+            .map(|change| vec![true; change.replacements.len()])
+            .collect();
+
+        let mut rows = Vec::new();
+        for location in workflow.locations() {
+            let indexes: Vec<usize> = (0..changes.len())
+                .filter(|i| changes[*i].location == location.name)
+                .collect();
+            rows.push(Row::Location(location.name.clone(), indexes.clone()));
+            for index in indexes {
+                rows.push(Row::File(index));
+                for replacement in 0..changes[index].replacements.len() {
+                    rows.push(Row::Match(index, replacement));
+                }
+            }
+        }
+
+        Ok(Self {
+            workflow,
+            changes,
+            contents,
+            selected,
+            rows,
+            state: ListState::default().with_selected(Some(0)),
+            scroll: 0,
+        })
+    }
+
+    /// Run the event loop, returns `true` when the changes should be applied
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<bool> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(false),
+                KeyCode::Char('a') | KeyCode::Enter => return Ok(true),
+                KeyCode::Char(' ') => self.toggle(),
+                KeyCode::Down | KeyCode::Char('j') => self.select(1),
+                KeyCode::Up | KeyCode::Char('k') => self.select(-1),
+                KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+                KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+                _ => {}
+            }
+        }
+    }
+
+    fn select(&mut self, offset: isize) {
+        let current = self.state.selected().unwrap_or_default();
+        let next = current
+            .saturating_add_signed(offset)
+            .min(self.rows.len().saturating_sub(1));
+        self.state.select(Some(next));
+        self.scroll = 0;
+    }
+
+    /// Replacements (change, replacement) covered by a row
+    fn covered(&self, row: &Row) -> Vec<(usize, usize)> {
+        match row {
+            Row::Location(_, indexes) => indexes
+                .iter()
+                .flat_map(|i| (0..self.selected[*i].len()).map(move |j| (*i, j)))
+                .collect(),
+            Row::File(i) => (0..self.selected[*i].len()).map(|j| (*i, j)).collect(),
+            Row::Match(i, j) => vec![(*i, *j)],
+        }
+    }
+
+    fn toggle(&mut self) {
+        let Some(row) = self.state.selected().and_then(|i| self.rows.get(i)) else {
+            return;
+        };
+        let covered = self.covered(row);
+        let value = !covered.iter().all(|(i, j)| self.selected[*i][*j]);
+        for (i, j) in covered {
+            self.selected[i][j] = value;
+        }
+    }
+
+    /// Changes with only the approved replacements
+    fn approved(&self) -> Vec<Change> {
+        self.changes
+            .iter()
+            .zip(&self.selected)
+            .map(|(change, selected)| Change {
+                replacements: change
+                    .replacements
+                    .iter()
+                    .zip(selected)
+                    .filter(|(_, selected)| **selected)
+                    .map(|(replacement, _)| replacement.clone())
+                    .collect(),
+                ..change.clone()
+            })
+            .collect()
+    }
+
+    fn checkbox(&self, row: &Row) -> &'static str {
+        let covered = self.covered(row);
+        let count = covered
+            .iter()
+            .filter(|(i, j)| self.selected[*i][*j])
+            .count();
+        if covered.is_empty() {
+            "   "
+        } else if count == covered.len() {
+            "[x]"
+        } else if count == 0 {
+            "[ ]"
+        } else {
+            "[-]"
+        }
+    }
+
+    fn item(&self, row: &Row) -> ListItem<'static> {
+        let checkbox = self.checkbox(row);
+        let line = match row {
+            Row::Location(name, indexes) if indexes.is_empty() => Line::from(vec![
+                Span::raw(format!("{checkbox} ")),
+                Span::styled(name.clone(), Style::new().add_modifier(Modifier::BOLD)),
+                Span::styled(" (no matches)", Style::new().fg(Color::DarkGray)),
+            ]),
+            Row::Location(name, indexes) => Line::from(vec![
+                Span::raw(format!("{checkbox} ")),
+                Span::styled(name.clone(), Style::new().add_modifier(Modifier::BOLD)),
+                Span::styled(
+                    format!(" ({} files)", indexes.len()),
+                    Style::new().fg(Color::DarkGray),
+                ),
+            ]),
+            Row::File(i) => {
+                let path = &self.changes[*i].path;
+                let relative = path.strip_prefix(self.workflow.root()).unwrap_or(path);
+                Line::from(vec![
+                    Span::raw(format!("  {checkbox} ")),
+                    Span::styled(relative.display().to_string(), Style::new().fg(Color::Cyan)),
+                ])
+            }
+            Row::Match(i, j) => {
+                let replacement = &self.changes[*i].replacements[*j];
+                Line::from(vec![
+                    Span::raw(format!("    {checkbox} ")),
+                    Span::styled(
+                        format!("L{} ", line_number(&self.contents[*i], replacement.start)),
+                        Style::new().fg(Color::DarkGray),
+                    ),
+                    Span::styled(replacement.old.clone(), Style::new().fg(Color::Red)),
+                    Span::raw(" → "),
+                    Span::styled(replacement.new.clone(), Style::new().fg(Color::Green)),
+                ])
+            }
+        };
+        ListItem::new(line)
+    }
+
+    /// Diff of the selected replacements covered by the current row
+    fn preview(&self) -> Vec<Line<'static>> {
+        let Some(row) = self.state.selected().and_then(|i| self.rows.get(i)) else {
+            return Vec::new();
+        };
+        // A single match is previewed even when it is not selected
+        let only_selected = !matches!(row, Row::Match(..));
+
+        let mut lines = Vec::new();
+        let covered = self.covered(row);
+        let mut indexes: Vec<usize> = covered.iter().map(|(i, _)| *i).collect();
+        indexes.dedup();
+        for i in indexes {
+            let replacements: Vec<&Replacement> = covered
+                .iter()
+                .filter(|(c, j)| *c == i && (!only_selected || self.selected[i][*j]))
+                .map(|(_, j)| &self.changes[i].replacements[*j])
+                .collect();
+            if replacements.is_empty() {
+                continue;
+            }
+            lines.push(Line::styled(
+                self.changes[i].path.display().to_string(),
+                Style::new().add_modifier(Modifier::BOLD),
+            ));
+            lines.extend(diff(&self.contents[i], &replacements));
+            lines.push(Line::default());
+        }
+        if lines.is_empty() {
+            lines.push(Line::styled(
+                "No changes selected",
+                Style::new().fg(Color::DarkGray),
+            ));
+        }
+        lines
+    }
+
+    fn draw(&mut self, frame: &mut ratatui::Frame) {
+        let [main, help] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(1)]).areas(frame.area());
+        let [left, right] =
+            Layout::horizontal([Constraint::Percentage(45), Constraint::Percentage(55)])
+                .areas(main);
+
+        let items: Vec<ListItem> = self.rows.iter().map(|row| self.item(row)).collect();
+        let list = List::new(items)
+            .block(Block::default().borders(Borders::ALL).title(" Locations "))
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(list, left, &mut self.state);
+
+        let preview = Paragraph::new(self.preview())
+            .block(Block::default().borders(Borders::ALL).title(" Preview "))
+            .scroll((self.scroll, 0));
+        frame.render_widget(preview, right);
+
+        let selected: usize = self.selected.iter().flatten().filter(|s| **s).count();
+        let total: usize = self.selected.iter().map(|s| s.len()).sum();
+        frame.render_widget(
+            Paragraph::new(format!(
+                " {selected}/{total} selected  ↑/↓ move  space toggle  PgUp/PgDn scroll  a apply  q quit"
+            ))
+            .style(Style::new().fg(Color::DarkGray)),
+            help,
+        );
+    }
+}
+
+/// Line number (1-based) of an offset in the content
+fn line_number(content: &str, offset: usize) -> usize {
+    content[..offset].matches('\n').count() + 1
+}
+
+/// Diff hunks with context for sorted replacements in the content
+fn diff(content: &str, replacements: &[&Replacement]) -> Vec<Line<'static>> {
+    // Group replacements by the lines they touch
+    let mut hunks: Vec<(usize, usize, Vec<&Replacement>)> = Vec::new();
+    for replacement in replacements {
+        let start = content[..replacement.start]
+            .rfind('\n')
+            .map_or(0, |i| i + 1);
+        let end = content[replacement.end..]
+            .find('\n')
+            .map_or(content.len(), |i| replacement.end + i);
+        match hunks.last_mut() {
+            Some((_, hunk_end, grouped)) if start <= *hunk_end => {
+                *hunk_end = end.max(*hunk_end);
+                grouped.push(replacement);
+            }
+            _ => hunks.push((start, end, vec![replacement])),
+        }
+    }
+
+    let dim = Style::new().fg(Color::DarkGray);
+    let mut lines = Vec::new();
+    for (start, end, grouped) in hunks {
+        let number = line_number(content, start);
+        lines.push(Line::styled(format!("@@ line {number} @@"), dim));
+
+        let before: Vec<&str> = content[..start].lines().rev().take(CONTEXT_LINES).collect();
+        for line in before.into_iter().rev() {
+            lines.push(Line::styled(format!("  {line}"), dim));
+        }
+
+        let old = &content[start..end];
+        let mut new = old.to_string();
+        for replacement in grouped.iter().rev() {
+            new.replace_range(
+                replacement.start - start..replacement.end - start,
+                &replacement.new,
+            );
+        }
+        for line in old.lines() {
+            lines.push(Line::styled(
+                format!("- {line}"),
+                Style::new().fg(Color::Red),
+            ));
+        }
+        for line in new.lines() {
+            lines.push(Line::styled(
+                format!("+ {line}"),
+                Style::new().fg(Color::Green),
+            ));
+        }
+
+        let after = content[end..].strip_prefix('\n').unwrap_or_default();
+        for line in after.lines().take(CONTEXT_LINES) {
+            lines.push(Line::styled(format!("  {line}"), dim));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn replacement(content: &str, old: &str, new: &str) -> Replacement {
+        let start = content.find(old).unwrap();
+        Replacement {
+            start,
+            end: start + old.len(),
+            old: old.to_string(),
+            new: new.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let content = "[package]\nname = \"a\"\nversion = \"1.2.3\"\nedition = \"2024\"\n";
+        let replacement = replacement(content, "1.2.3", "1.3.0");
+        let lines: Vec<String> = diff(content, &[&replacement])
+            .iter()
+            .map(|line| line.to_string())
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "@@ line 3 @@",
+                "  [package]",
+                "  name = \"a\"",
+                "- version = \"1.2.3\"",
+                "+ version = \"1.3.0\"",
+                "  edition = \"2024\"",
+            ]
+        );
+    }
+}
@@ -3,7 +3,10 @@ use console::style;
 use log::{debug, info, warn};
 use std::path::PathBuf;
 
-use crate::config::{BumpMode, LocationPattern};
+use crate::config::{BumpMode, LocationPattern, LocationType, is_excluded};
+use crate::error::Error;
+use crate::interactive::{Approval, approve_replacement};
+use crate::rollback::Journal;
 
 #[derive(Debug, Clone)]
 pub enum WorkflowMode {
@@ -11,6 +14,7 @@ pub enum WorkflowMode {
         name: Option<String>,
         version: Option<String>,
         repository: Option<String>,
+        host: Option<String>,
         language_ecosystems: Vec<String>,
         enable_defaults: Option<bool>,
     },
@@ -19,10 +23,120 @@ pub enum WorkflowMode {
         mode: BumpMode,
         /// Version to set
         version: semver::Version,
+        /// Counter / build number to set
+        counter: Option<u64>,
     },
     Display,
+    /// Report locations which differ from the configured version
+    Check,
+    /// Report if the captured versions agree with each other
+    Status,
+    /// Report versions which are not covered by any location
+    Audit,
 }
 
+/// Summary of the files and matches a workflow processed
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    /// Files with matches (or that were updated)
+    pub files: Vec<PathBuf>,
+    /// Number of matches (or changes)
+    pub matches: usize,
+    /// Files excluded from locations during an interactive bump (location, path)
+    pub excluded: Vec<(String, PathBuf)>,
+}
+
+/// Captures of a pattern of a location in a file
+#[derive(Debug)]
+pub struct Capture<'h> {
+    /// Index of the regex of the location which captured
+    pub pattern: usize,
+    captures: regex::Captures<'h>,
+}
+
+impl<'h> std::ops::Deref for Capture<'h> {
+    type Target = regex::Captures<'h>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.captures
+    }
+}
+
+/// Planned replacement of a captured value in a file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replacement {
+    /// Start offset in the file
+    pub start: usize,
+    /// End offset in the file
+    pub end: usize,
+    /// Current value
+    pub old: String,
+    /// New value
+    pub new: String,
+}
+
+impl Replacement {
+    /// If two replacements overlap in the file
+    pub fn overlaps(&self, other: &Replacement) -> bool {
+        self.start < other.end && other.start < self.end
+    }
+}
+
+/// Captured value which does not match the expected version
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Drift {
+    /// Name of the location
+    pub location: String,
+    /// File the value was found in
+    pub path: PathBuf,
+    /// Line (1-based) of the value
+    pub line: usize,
+    /// Column (1-based, in characters) of the value
+    pub column: usize,
+    /// Value found in the file
+    pub found: String,
+    /// Expected value
+    pub expected: String,
+}
+
+/// Version captured by a location
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Occurrence {
+    /// Name of the location
+    pub location: String,
+    /// File the version was found in
+    pub path: PathBuf,
+    /// Line (1-based) of the version
+    pub line: usize,
+    /// Version found in the file
+    pub value: String,
+}
+
+/// Number of files and matches of a location
+#[derive(Debug, Clone, Default)]
+pub struct LocationStatus {
+    /// Name of the location
+    pub name: String,
+    /// Files with matches
+    pub files: usize,
+    /// Number of matches
+    pub matches: usize,
+}
+
+/// Planned replacements in a file matched by a location
+#[derive(Debug, Clone)]
+pub struct Change {
+    /// Name of the location
+    pub location: String,
+    /// File to update
+    pub path: PathBuf,
+    /// Replacements in the file
+    pub replacements: Vec<Replacement>,
+}
+
+/// Edits of plugin locations by location name and file
+type PluginEdits = std::sync::Mutex<std::collections::HashMap<(String, PathBuf), Vec<Replacement>>>;
+
 #[derive(Debug, Clone)]
 pub struct Workflow {
     /// Project Root
@@ -31,6 +145,15 @@ pub struct Workflow {
     mode: WorkflowMode,
     /// Locations to update
     locations: Vec<LocationPattern>,
+    /// Release date used for date based locations
+    date: chrono::DateTime<chrono::Utc>,
+    /// Approve every replacement interactively
+    interactive: bool,
+    /// Current version of the project (sent to plugins)
+    current: Option<String>,
+    /// Edits of the plugin locations by location name and file, so every
+    /// plugin runs once per file
+    plugin_edits: std::sync::Arc<PluginEdits>,
 }
 
 impl Workflow {
@@ -38,133 +161,714 @@ impl Workflow {
         WorkflowBuilder::default()
     }
 
-    pub fn display(&self) -> Result<()> {
+    /// Project root of the workflow
+    pub fn root(&self) -> &std::path::Path {
+        &self.root
+    }
+
+    /// Locations processed by the workflow
+    pub fn locations(&self) -> &[LocationPattern] {
+        &self.locations
+    }
+
+    pub fn display(&self) -> Result<Summary> {
         use std::sync::{Arc, Mutex};
-        let file_count = Arc::new(Mutex::new(0));
+        let file_count = Arc::new(Mutex::new(Vec::new()));
         let match_count = Arc::new(Mutex::new(0));
 
         let fc = file_count.clone();
         let mc = match_count.clone();
 
-        self.process(move |path, captures| {
+        self.process(move |location, path, captures| {
+            if location.r#type.is_plugin() {
+                let replacements = self.replacements(location, &path, &captures)?;
+                if replacements.is_empty() {
+                    return Ok(());
+                }
+                fc.lock().unwrap().push(path.clone());
+                println!("  {} {}", style("📄").dim(), style(path.display()).cyan());
+                for replacement in replacements {
+                    *mc.lock().unwrap() += 1;
+                    match &self.mode {
+                        WorkflowMode::Bump { .. } => println!(
+                            "     {} {} {} {}",
+                            style("→").dim(),
+                            style(&replacement.old).red(),
+                            style("→").green(),
+                            style(&replacement.new).green().bold()
+                        ),
+                        _ => println!(
+                            "     {} {} (line position: {})",
+                            style("→").dim(),
+                            style(&replacement.old).red().bold(),
+                            style(replacement.start).dim()
+                        ),
+                    }
+                }
+                println!();
+                return Ok(());
+            }
             if !captures.is_empty() {
-                *fc.lock().unwrap() += 1;
+                fc.lock().unwrap().push(path.clone());
 
                 // Print file header
                 println!("  {} {}", style("📄").dim(), style(path.display()).cyan());
 
                 for capture in captures {
-                    *mc.lock().unwrap() += 1;
-                    let data = capture.get(1).unwrap();
-                    let start = data.start();
+                    for (component, data) in capture_groups(&capture) {
+                        *mc.lock().unwrap() += 1;
+                        let start = data.start();
 
-                    match &self.mode {
-                        WorkflowMode::Display => {
-                            println!(
-                                "     {} {} (line position: {})",
-                                style("→").dim(),
-                                style(data.as_str()).red().bold(),
-                                style(start).dim()
-                            );
-                        }
-                        WorkflowMode::Bump { version, .. } => {
-                            println!(
-                                "     {} {} {} {}",
-                                style("→").dim(),
-                                style(data.as_str()).red(),
-                                style("→").green(),
-                                style(version).green().bold()
-                            );
-                        }
-                        _ => {}
-                    };
+                        match &self.mode {
+                            WorkflowMode::Display => {
+                                println!(
+                                    "     {} {} (line position: {})",
+                                    style("→").dim(),
+                                    style(data.as_str()).red().bold(),
+                                    style(start).dim()
+                                );
+                            }
+                            WorkflowMode::Bump { version, .. } => {
+                                println!(
+                                    "     {} {} {} {}",
+                                    style("→").dim(),
+                                    style(data.as_str()).red(),
+                                    style("→").green(),
+                                    style(self.render(
+                                        location,
+                                        capture.pattern,
+                                        component,
+                                        data.as_str(),
+                                        version
+                                    )?)
+                                    .green()
+                                    .bold()
+                                );
+                            }
+                            _ => {}
+                        };
+                    }
                 }
                 println!();
             }
             Ok(())
         })?;
 
-        let files = *file_count.lock().unwrap();
+        let files = file_count.lock().unwrap().clone();
         let matches = *match_count.lock().unwrap();
 
         println!("{}", style("─".repeat(60)).dim());
         println!(
             "  {} files with {} version references",
-            style(files).cyan().bold(),
+            style(files.len()).cyan().bold(),
             style(matches).cyan().bold()
         );
 
-        Ok(())
+        Ok(Summary {
+            files,
+            matches,
+            ..Default::default()
+        })
     }
 
-    /// Patch Mode - Update the versions
-    pub async fn patch(&self) -> Result<()> {
+    /// Patch Mode - Update the versions, files are written with the journal
+    /// of the bump
+    pub async fn patch(&self, journal: &Journal) -> Result<Summary> {
         use std::sync::{Arc, Mutex};
-        let file_count = Arc::new(Mutex::new(0));
+        let file_count = Arc::new(Mutex::new(Vec::new()));
         let update_count = Arc::new(Mutex::new(0));
+        let excluded = Arc::new(Mutex::new(Vec::new()));
 
         let fc = file_count.clone();
         let uc = update_count.clone();
+        let ex = excluded.clone();
 
-        self.process(move |path, captures| {
-            let mut content = std::fs::read_to_string(&path)?;
-            let mut file_updated = false;
+        self.process(move |location, path, captures| {
+            let mut content = std::fs::read(&path)?;
+            let mut replacements = match location.r#type.is_plugin() {
+                true => self.plugin_replacements(location, &path, Some(&content))?,
+                false => self.replacements(location, &path, &captures)?,
+            };
 
-            for capture in captures {
-                let data = capture.get(1).unwrap();
-                let start = data.start();
-                let end = data.end();
-
-                if let WorkflowMode::Bump { version, .. } = &self.mode {
-                    if !file_updated {
-                        println!("  {} {}", style("📝").cyan(), style(path.display()).bold());
-                        file_updated = true;
-                        *fc.lock().unwrap() += 1;
+            if self.interactive && !replacements.is_empty() {
+                let text = text(&content);
+                let mut approved = Vec::new();
+                for replacement in replacements {
+                    match approve_replacement(&path, &text, &replacement)? {
+                        Approval::Accept => approved.push(replacement),
+                        Approval::Skip => {}
+                        Approval::SkipFile => break,
+                        Approval::Exclude => {
+                            let relative = path.strip_prefix(&self.root).unwrap_or(&path);
+                            ex.lock()
+                                .unwrap()
+                                .push((location.name.clone(), relative.to_path_buf()));
+                            approved.clear();
+                            break;
+                        }
                     }
+                }
+                replacements = approved;
+            }
+
+            if replacements.is_empty() {
+                return Ok(());
+            }
 
-                    println!(
-                        "     {} {} {} {}",
-                        style("✓").green(),
-                        style(data.as_str()).red(),
-                        style("→").dim(),
-                        style(version.clone()).green().bold()
-                    );
+            println!("  {} {}", style("📝").cyan(), style(path.display()).bold());
+            fc.lock().unwrap().push(path.clone());
 
-                    content.replace_range(start..end, version.to_string().as_str());
-                    *uc.lock().unwrap() += 1;
-                };
+            for replacement in &replacements {
+                println!(
+                    "     {} {} {} {}",
+                    style("✓").green(),
+                    style(&replacement.old).red(),
+                    style("→").dim(),
+                    style(&replacement.new).green().bold()
+                );
+                *uc.lock().unwrap() += 1;
             }
 
+            apply_replacements(&mut content, &replacements);
+
             // Write content back to file
-            if file_updated {
-                std::fs::write(&path, content)?;
-                println!();
-            }
+            self.write(journal, &path, content)?;
+            println!();
 
             Ok(())
         })?;
 
-        let files = *file_count.lock().unwrap();
+        let files = file_count.lock().unwrap().clone();
         let updates = *update_count.lock().unwrap();
 
         println!("{}", style("─".repeat(60)).dim());
         println!(
             "  {} files updated with {} changes",
-            style(files).cyan().bold(),
+            style(files.len()).cyan().bold(),
             style(updates).cyan().bold()
         );
 
+        let excluded = excluded.lock().unwrap().clone();
+        Ok(Summary {
+            files,
+            matches: updates,
+            excluded,
+        })
+    }
+
+    /// Plan Mode - Collect the replacements a bump would make without writing
+    pub fn plan(&self) -> Result<Vec<Change>> {
+        use std::sync::{Arc, Mutex};
+        let changes = Arc::new(Mutex::new(Vec::new()));
+
+        let ch = changes.clone();
+        self.process(move |location, path, captures| {
+            let replacements = self.replacements(location, &path, &captures)?;
+            if !replacements.is_empty() {
+                ch.lock().unwrap().push(Change {
+                    location: location.name.clone(),
+                    path,
+                    replacements,
+                });
+            }
+            Ok(())
+        })?;
+
+        let changes = changes.lock().unwrap().clone();
+        Ok(changes)
+    }
+
+    /// Apply planned changes, all replacements in a file are written at once
+    pub fn apply(&self, changes: &[Change], journal: &Journal) -> Result<Summary> {
+        let mut files: Vec<(PathBuf, Vec<Replacement>)> = Vec::new();
+        for change in changes {
+            let index = match files.iter().position(|(path, _)| path == &change.path) {
+                Some(index) => index,
+                None => {
+                    files.push((change.path.clone(), Vec::new()));
+                    files.len() - 1
+                }
+            };
+            let replacements = &mut files[index].1;
+            for replacement in &change.replacements {
+                if !replacements.iter().any(|r| r.overlaps(replacement)) {
+                    replacements.push(replacement.clone());
+                }
+            }
+        }
+
+        let mut summary = Summary::default();
+        for (path, mut replacements) in files {
+            if replacements.is_empty() {
+                continue;
+            }
+            replacements.sort_by_key(|r| r.start);
+
+            let mut content = std::fs::read(&path)?;
+            apply_replacements(&mut content, &replacements);
+            self.write(journal, &path, content)?;
+
+            summary.matches += replacements.len();
+            summary.files.push(path);
+        }
+        Ok(summary)
+    }
+
+    /// Replacements for the captures of a location in a file (sorted by offset)
+    ///
+    /// Plugin locations return the edits of their plugin, outside of a bump
+    /// the plugin is asked to write the current version.
+    pub fn replacements(
+        &self,
+        location: &LocationPattern,
+        path: &std::path::Path,
+        captures: &[Capture],
+    ) -> Result<Vec<Replacement>> {
+        if location.r#type.is_plugin() {
+            return self.plugin_replacements(location, path, None);
+        }
+
+        let WorkflowMode::Bump { version, .. } = &self.mode else {
+            return Ok(Vec::new());
+        };
+
+        let mut replacements: Vec<Replacement> = Vec::new();
+        for capture in captures {
+            for (component, data) in capture_groups(capture) {
+                let replacement = Replacement {
+                    start: data.start(),
+                    end: data.end(),
+                    old: data.as_str().to_string(),
+                    new: self.render(
+                        location,
+                        capture.pattern,
+                        component,
+                        data.as_str(),
+                        version,
+                    )?,
+                };
+                // Multiple patterns can capture the same range
+                if !replacements.iter().any(|r| r.overlaps(&replacement)) {
+                    replacements.push(replacement);
+                }
+            }
+        }
+        replacements.sort_by_key(|r| r.start);
+        Ok(replacements)
+    }
+
+    /// Render the new value for a capture group in a location
+    ///
+    /// The script of the pattern (`pattern` is the index of its regex) or of
+    /// the location takes priority over the type of the location.
+    pub fn render(
+        &self,
+        location: &LocationPattern,
+        pattern: usize,
+        component: VersionComponent,
+        capture: &str,
+        version: &semver::Version,
+    ) -> Result<String> {
+        if let Some(script) = location.scripts.get(pattern).and_then(Option::as_ref) {
+            return script
+                .render(capture, version)
+                .map_err(|e| anyhow::anyhow!("Location `{}`: {}", location.name, e));
+        }
+        match location.r#type {
+            LocationType::Version | LocationType::Plugin => Ok(component.render(version)),
+            LocationType::Date | LocationType::Year | LocationType::Timestamp => {
+                let format = location
+                    .format
+                    .as_deref()
+                    .or(location.r#type.default_format())
+                    .unwrap_or_default();
+                // An invalid format would panic while formatting
+                crate::config::date_format_pattern(&location.name, format)?;
+                Ok(self.date.format(format).to_string())
+            }
+            LocationType::Counter => {
+                let counter = match &self.mode {
+                    WorkflowMode::Bump { counter, .. } => *counter,
+                    _ => None,
+                };
+                match &location.format {
+                    Some(format) => Ok(evaluate_counter(format, version, counter)?.to_string()),
+                    None => counter.map(|c| c.to_string()).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Location `{}` uses a counter but `counter` is not set in the configuration",
+                            location.name
+                        )
+                    }),
+                }
+            }
+        }
+    }
+
+    /// Edits of the plugin of a location for a file, reading the file when
+    /// its `content` is not given. Outside of a bump the plugin is asked to
+    /// write the current version.
+    fn plugin_replacements(
+        &self,
+        location: &LocationPattern,
+        path: &std::path::Path,
+        content: Option<&[u8]>,
+    ) -> Result<Vec<Replacement>> {
+        let key = (location.name.clone(), path.to_path_buf());
+        if let Some(replacements) = self.plugin_edits.lock().unwrap().get(&key) {
+            return Ok(replacements.clone());
+        }
+
+        let version = match &self.mode {
+            WorkflowMode::Bump { version, .. } => Some(version.clone()),
+            _ => self
+                .current
+                .as_deref()
+                .and_then(|v| semver::Version::parse(v).ok()),
+        };
+        let Some(version) = version else {
+            return Ok(Vec::new());
+        };
+        let content = match content {
+            Some(content) => std::borrow::Cow::Borrowed(content),
+            None => std::borrow::Cow::Owned(std::fs::read(path)?),
+        };
+        let replacements = crate::plugins::replacements(
+            &self.root,
+            path,
+            &content,
+            self.current.as_deref(),
+            &version,
+            location,
+        )?;
+        self.plugin_edits
+            .lock()
+            .unwrap()
+            .insert(key, replacements.clone());
+        Ok(replacements)
+    }
+
+    /// Write a patched file, the plugin edits of the file are outdated
+    fn write(&self, journal: &Journal, path: &std::path::Path, content: Vec<u8>) -> Result<()> {
+        journal.write(path, content)?;
+        self.plugin_edits
+            .lock()
+            .unwrap()
+            .retain(|(_, edited), _| edited != path);
         Ok(())
     }
 
+    /// Scan Mode - Collect every version captured by the version locations
+    pub fn scan(&self) -> Result<Vec<(PathBuf, String)>> {
+        Ok(self
+            .occurrences()?
+            .1
+            .into_iter()
+            .map(|occurrence| (occurrence.path, occurrence.value))
+            .collect())
+    }
+
+    /// Spans of the values captured by the locations in each file
+    pub fn covered(&self) -> Result<Vec<(PathBuf, std::ops::Range<usize>)>> {
+        use std::sync::{Arc, Mutex};
+        let covered = Arc::new(Mutex::new(Vec::new()));
+
+        let cv = covered.clone();
+        self.process(move |location, path, captures| {
+            if location.r#type.is_plugin() {
+                for replacement in self.replacements(location, &path, &captures)? {
+                    cv.lock()
+                        .unwrap()
+                        .push((path.clone(), replacement.start..replacement.end));
+                }
+                return Ok(());
+            }
+            for capture in &captures {
+                for (_, data) in capture_groups(capture) {
+                    cv.lock().unwrap().push((path.clone(), data.range()));
+                }
+            }
+            Ok(())
+        })?;
+
+        let covered = covered.lock().unwrap().clone();
+        Ok(covered)
+    }
+
+    /// Files and matches per location, and every version captured by the
+    /// version locations
+    pub fn occurrences(&self) -> Result<(Vec<LocationStatus>, Vec<Occurrence>)> {
+        use std::sync::{Arc, Mutex};
+        let locations = Arc::new(Mutex::new(
+            self.locations
+                .iter()
+                .filter(|location| !location.regexes.is_empty() || location.r#type.is_plugin())
+                .map(|location| LocationStatus {
+                    name: location.name.clone(),
+                    ..Default::default()
+                })
+                .collect::<Vec<_>>(),
+        ));
+        let found = Arc::new(Mutex::new(Vec::new()));
+
+        let ls = locations.clone();
+        let fd = found.clone();
+        self.process(move |location, path, captures| {
+            let matches = match location.r#type.is_plugin() {
+                true => self.replacements(location, &path, &captures)?.len(),
+                false => captures.len(),
+            };
+            if let Some(status) = ls
+                .lock()
+                .unwrap()
+                .iter_mut()
+                .find(|status| status.name == location.name && matches > 0)
+            {
+                status.files += 1;
+                status.matches += matches;
+            }
+            if !location.r#type.is_version() {
+                return Ok(());
+            }
+
+            let content = std::fs::read_to_string(&path)?;
+            for capture in &captures {
+                for (component, data) in capture_groups(capture) {
+                    if component == VersionComponent::Version {
+                        fd.lock().unwrap().push(Occurrence {
+                            location: location.name.clone(),
+                            path: path.clone(),
+                            line: content[..data.start()].matches('\n').count() + 1,
+                            value: data.as_str().to_string(),
+                        });
+                    }
+                }
+            }
+            Ok(())
+        })?;
+
+        let locations = locations.lock().unwrap().clone();
+        let found = found.lock().unwrap().clone();
+        Ok((locations, found))
+    }
+
+    /// Locations which match more or less often than expected (`required`
+    /// and `expect` in the configuration)
+    pub fn violations(&self) -> Result<Vec<Error>> {
+        // Nothing to count, so the files (and plugins) are not scanned
+        if self
+            .locations
+            .iter()
+            .all(|location| location.expected_matches().is_none())
+        {
+            return Ok(Vec::new());
+        }
+        let (locations, _) = self.occurrences()?;
+        let mut violations = Vec::new();
+        for location in &self.locations {
+            let Some(expected) = location.expected_matches() else {
+                continue;
+            };
+            let matches = locations
+                .iter()
+                .find(|status| status.name == location.name)
+                .map_or(0, |status| status.matches);
+            if !expected.contains(matches) {
+                violations.push(Error::UnexpectedMatchCount(
+                    location.name.clone(),
+                    matches,
+                    expected.to_string(),
+                ));
+            }
+        }
+        Ok(violations)
+    }
+
+    /// Fail if any location matches more or less often than expected
+    pub fn validate(&self) -> Result<()> {
+        let violations = self.violations()?;
+        match violations.len() {
+            0 => Ok(()),
+            1 => Err(violations[0].clone().into()),
+            _ => Err(anyhow::anyhow!(
+                "{}",
+                violations
+                    .iter()
+                    .map(|v| v.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            )),
+        }
+    }
+
+    /// Status Mode - Report if the captured versions agree with the canonical
+    /// version (or the most common version if none is set)
+    pub fn status(&self, canonical: Option<&str>) -> Result<Summary> {
+        let (locations, found) = self.occurrences()?;
+
+        let found_versions: Vec<(PathBuf, String)> = found
+            .iter()
+            .map(|o| (o.path.clone(), o.value.clone()))
+            .collect();
+        let (canonical, label) = match canonical {
+            Some(version) => (Some(version.to_string()), "canonical"),
+            None => (
+                common_version(&found_versions).map(|v| v.to_string()),
+                "most common",
+            ),
+        };
+        let is_canonical = |value: &str| match (&canonical, semver::Version::parse(value)) {
+            (Some(canonical), Ok(version)) => semver::Version::parse(canonical)
+                .map(|c| c == version)
+                .unwrap_or(canonical == value),
+            (Some(canonical), Err(_)) => canonical == value,
+            (None, _) => false,
+        };
+
+        // Group by distinct version, the canonical version first
+        let mut groups: Vec<(String, Vec<&Occurrence>)> = Vec::new();
+        for occurrence in &found {
+            match groups
+                .iter_mut()
+                .find(|(value, _)| *value == occurrence.value)
+            {
+                Some((_, occurrences)) => occurrences.push(occurrence),
+                None => groups.push((occurrence.value.clone(), vec![occurrence])),
+            }
+        }
+        groups.sort_by(|(a, a_found), (b, b_found)| {
+            is_canonical(b)
+                .cmp(&is_canonical(a))
+                .then(b_found.len().cmp(&a_found.len()))
+        });
+
+        println!("  {}", style("Versions").bold());
+        if groups.is_empty() {
+            println!("    {} No versions found", style("⚠").yellow());
+        }
+        for (value, occurrences) in &groups {
+            let mut files: Vec<&PathBuf> = occurrences.iter().map(|o| &o.path).collect();
+            files.sort();
+            files.dedup();
+            let counts = format!("{} matches in {} files", occurrences.len(), files.len());
+
+            if is_canonical(value) {
+                println!(
+                    "    {} {} ({})  {}",
+                    style("✓").green(),
+                    style(value).green().bold(),
+                    label,
+                    style(counts).dim()
+                );
+                continue;
+            }
+            println!(
+                "    {} {}  {}",
+                style("✗").red(),
+                style(value).red().bold(),
+                style(counts).dim()
+            );
+            for occurrence in occurrences {
+                let path = occurrence
+                    .path
+                    .strip_prefix(&self.root)
+                    .unwrap_or(&occurrence.path);
+                println!(
+                    "       {} {}:{} ({})",
+                    style("→").dim(),
+                    style(path.display()).cyan(),
+                    occurrence.line,
+                    occurrence.location
+                );
+            }
+        }
+        println!();
+
+        println!("  {}", style("Locations").bold());
+        let width = locations.iter().map(|l| l.name.len()).max().unwrap_or(0);
+        for location in &locations {
+            let counts = format!("{} files, {} matches", location.files, location.matches);
+            if location.matches == 0 {
+                println!(
+                    "    {} {:<width$}  {} {}",
+                    style("⚠").yellow(),
+                    location.name,
+                    style(counts).dim(),
+                    style("(matched nothing)").yellow()
+                );
+            } else {
+                println!(
+                    "    {} {:<width$}  {}",
+                    style("✓").green(),
+                    location.name,
+                    style(counts).dim()
+                );
+            }
+        }
+
+        let mut files: Vec<PathBuf> = found.iter().map(|o| o.path.clone()).collect();
+        files.sort();
+        files.dedup();
+        Ok(Summary {
+            files,
+            matches: locations.iter().map(|l| l.matches).sum(),
+            excluded: Vec::new(),
+        })
+    }
+
+    /// Check Mode - Captured values which differ from the version of the workflow
+    ///
+    /// Date based locations are skipped as they change on every release.
+    pub fn drift(&self) -> Result<Vec<Drift>> {
+        use std::sync::{Arc, Mutex};
+        let drift = Arc::new(Mutex::new(Vec::new()));
+
+        let dr = drift.clone();
+        self.process(move |location, path, captures| {
+            if !matches!(
+                location.r#type,
+                LocationType::Version | LocationType::Counter | LocationType::Plugin
+            ) {
+                return Ok(());
+            }
+            let replacements = self.replacements(location, &path, &captures)?;
+            if replacements.iter().all(|r| r.old == r.new) {
+                return Ok(());
+            }
+
+            let content = std::fs::read(&path)?;
+            let content = text(&content);
+            for replacement in replacements {
+                if replacement.old == replacement.new {
+                    continue;
+                }
+                let line_start = content[..replacement.start]
+                    .rfind('\n')
+                    .map_or(0, |i| i + 1);
+                dr.lock().unwrap().push(Drift {
+                    location: location.name.clone(),
+                    path: path.clone(),
+                    line: content[..replacement.start].matches('\n').count() + 1,
+                    column: content[line_start..replacement.start].chars().count() + 1,
+                    found: replacement.old,
+                    expected: replacement.new,
+                });
+            }
+            Ok(())
+        })?;
+
+        let drift = drift.lock().unwrap().clone();
+        Ok(drift)
+    }
+
     pub fn process<F>(&self, action: F) -> Result<()>
     where
-        F: Fn(PathBuf, Vec<regex::Captures>) -> Result<()>,
+        F: Fn(&LocationPattern, PathBuf, Vec<Capture>) -> Result<()>,
     {
         for location in &self.locations {
             info!("Processing Location :: {}", location);
 
-            if location.regexes.is_empty() {
+            let plugin = location.r#type.is_plugin();
+            if location.regexes.is_empty() && !plugin {
                 warn!("No regexes found for location, skipping...");
                 continue;
             }
@@ -182,31 +886,40 @@ impl Workflow {
                     };
 
                     // Check if entry matches exclude patterns
-                    if location
-                        .excludes
-                        .iter()
-                        .any(|pattern| String::from(entry.to_str().unwrap()).contains(pattern))
-                    {
+                    if is_excluded(&location.excludes, &self.root, &entry) {
                         debug!("Excluded: {:?}", entry);
                         continue;
                     }
 
+                    // Plugins read the file themselves
+                    if plugin {
+                        action(location, entry, Vec::new())?;
+                        continue;
+                    }
+
                     // Load file
                     let file_contents = std::fs::read_to_string(&entry)?;
 
-                    let mut captures: Vec<regex::Captures> = Vec::new();
-                    location.regexes.iter().for_each(|regex| {
-                        regex.captures_iter(&file_contents).for_each(|capture| {
-                            captures.push(capture);
+                    let mut captures: Vec<Capture> = Vec::new();
+                    location
+                        .regexes
+                        .iter()
+                        .enumerate()
+                        .for_each(|(pattern, regex)| {
+                            regex.captures_iter(&file_contents).for_each(|found| {
+                                captures.push(Capture {
+                                    pattern,
+                                    captures: found,
+                                });
+                            });
                         });
-                    });
 
                     if captures.is_empty() {
                         debug!("No captures found in file, skipping...");
                         continue;
                     }
 
-                    action(entry, captures)?;
+                    action(location, entry, captures)?;
                 }
             }
         }
@@ -220,6 +933,9 @@ pub struct WorkflowBuilder {
     root: Option<PathBuf>,
     mode: Option<WorkflowMode>,
     locations: Vec<LocationPattern>,
+    date: Option<chrono::DateTime<chrono::Utc>>,
+    interactive: bool,
+    current: Option<String>,
 }
 
 impl Default for WorkflowBuilder {
@@ -228,6 +944,9 @@ impl Default for WorkflowBuilder {
             root: Some(PathBuf::from("./")),
             mode: Some(WorkflowMode::Display),
             locations: Vec::new(),
+            date: None,
+            interactive: false,
+            current: None,
         }
     }
 }
@@ -247,6 +966,24 @@ impl WorkflowBuilder {
         self
     }
 
+    /// Set the release date (defaults to now)
+    pub fn date(mut self, date: Option<chrono::DateTime<chrono::Utc>>) -> Self {
+        self.date = date;
+        self
+    }
+
+    /// Approve every replacement interactively while patching
+    pub fn interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
+    /// Set the current version of the project (sent to plugins)
+    pub fn current(mut self, version: Option<String>) -> Self {
+        self.current = version;
+        self
+    }
+
     /// Add locations to the workflow
     pub fn locations(mut self, locations: Vec<LocationPattern>) -> Result<Self> {
         // Compile regexes
@@ -255,7 +992,7 @@ impl WorkflowBuilder {
             if new_location.regexes.is_empty() {
                 // TODO: Support replacement ${...} syntax
 
-                new_location.regexes = LocationPattern::regexes(&location.patterns)?;
+                new_location.compile()?;
             }
 
             self.locations.push(new_location);
@@ -268,11 +1005,153 @@ impl WorkflowBuilder {
             root: self.root.expect("Root is required"),
             mode: self.mode.expect("Mode is required"),
             locations: self.locations,
+            date: self.date.unwrap_or_else(chrono::Utc::now),
+            interactive: self.interactive,
+            current: self.current,
+            plugin_edits: Default::default(),
         }
     }
 }
 
-pub(crate) fn update_version(version: &mut semver::Version, bump_mode: &BumpMode) {
+/// Component of a version that a capture group is replaced with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionComponent {
+    /// Full version (`version` / `semver` named groups or the first group)
+    Version,
+    /// Major version number (`major` named group)
+    Major,
+    /// Minor version number (`minor` named group)
+    Minor,
+    /// Patch version number (`patch` named group)
+    Patch,
+    /// Pre-release identifier (`pre` named group)
+    Prerelease,
+    /// Build metadata (`build` named group)
+    Build,
+}
+
+impl VersionComponent {
+    /// Named capture groups which map to a version component
+    pub const NAMES: [(&'static str, VersionComponent); 7] = [
+        ("version", VersionComponent::Version),
+        ("semver", VersionComponent::Version),
+        ("major", VersionComponent::Major),
+        ("minor", VersionComponent::Minor),
+        ("patch", VersionComponent::Patch),
+        ("pre", VersionComponent::Prerelease),
+        ("build", VersionComponent::Build),
+    ];
+
+    /// Render the component of the version
+    pub fn render(&self, version: &semver::Version) -> String {
+        match self {
+            VersionComponent::Version => version.to_string(),
+            VersionComponent::Major => version.major.to_string(),
+            VersionComponent::Minor => version.minor.to_string(),
+            VersionComponent::Patch => version.patch.to_string(),
+            VersionComponent::Prerelease => version.pre.to_string(),
+            VersionComponent::Build => version.build.to_string(),
+        }
+    }
+}
+
+/// Get the capture groups to replace and the version component for each.
+///
+/// Named groups (`version`, `major`, `minor`, ...) take priority, otherwise the
+/// first capture group is replaced with the full version.
+pub(crate) fn capture_groups<'h>(
+    capture: &regex::Captures<'h>,
+) -> Vec<(VersionComponent, regex::Match<'h>)> {
+    let mut groups: Vec<(VersionComponent, regex::Match<'h>)> = VersionComponent::NAMES
+        .iter()
+        .filter_map(|(name, component)| capture.name(name).map(|m| (*component, m)))
+        .collect();
+
+    if groups.is_empty() {
+        if let Some(data) = capture.get(1) {
+            groups.push((VersionComponent::Version, data));
+        }
+    }
+    groups.sort_by_key(|(_, m)| m.start());
+    groups
+}
+
+/// Evaluate a counter expression such as `{major}*10000+{minor}*100+{patch}`.
+///
+/// Only addition and multiplication of integers and the `{major}`, `{minor}`,
+/// `{patch}` and `{counter}` placeholders are supported.
+pub(crate) fn evaluate_counter(
+    expression: &str,
+    version: &semver::Version,
+    counter: Option<u64>,
+) -> Result<u64> {
+    let mut total: u64 = 0;
+    for term in expression.split('+') {
+        let mut product: u64 = 1;
+        for factor in term.split('*') {
+            let value = match factor.trim() {
+                "{major}" => version.major,
+                "{minor}" => version.minor,
+                "{patch}" => version.patch,
+                "{counter}" => counter.ok_or_else(|| {
+                    anyhow::anyhow!("Counter expression uses `{{counter}}` but it is not set")
+                })?,
+                number => number.parse::<u64>().map_err(|_| {
+                    anyhow::anyhow!("Invalid counter expression `{expression}`: `{number}`")
+                })?,
+            };
+            product = product
+                .checked_mul(value)
+                .ok_or_else(|| anyhow::anyhow!("Counter expression overflowed: {expression}"))?;
+        }
+        total = total
+            .checked_add(product)
+            .ok_or_else(|| anyhow::anyhow!("Counter expression overflowed: {expression}"))?;
+    }
+    Ok(total)
+}
+
+/// Apply sorted replacements to file content, from the end so earlier offsets stay valid
+pub(crate) fn apply_replacements(content: &mut Vec<u8>, replacements: &[Replacement]) {
+    for replacement in replacements.iter().rev() {
+        content.splice(replacement.start..replacement.end, replacement.new.bytes());
+    }
+}
+
+/// Contents of a file as text. Bytes of files which are not valid UTF-8 are
+/// shown as ASCII (or `.`), so offsets into the file stay valid.
+pub(crate) fn text(content: &[u8]) -> std::borrow::Cow<'_, str> {
+    match std::str::from_utf8(content) {
+        Ok(text) => std::borrow::Cow::Borrowed(text),
+        Err(_) => std::borrow::Cow::Owned(
+            content
+                .iter()
+                .map(|&byte| if byte.is_ascii() { byte as char } else { '.' })
+                .collect(),
+        ),
+    }
+}
+
+/// Pick the most common version (highest on a tie) from scanned versions
+pub(crate) fn common_version(found: &[(PathBuf, String)]) -> Option<semver::Version> {
+    let mut counts: Vec<(semver::Version, usize)> = Vec::new();
+    for (_, value) in found {
+        let Ok(version) = semver::Version::parse(value) else {
+            continue;
+        };
+        match counts.iter_mut().find(|(v, _)| *v == version) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((version, 1)),
+        }
+    }
+    counts
+        .into_iter()
+        .max_by(|(a, a_count), (b, b_count)| a_count.cmp(b_count).then(a.cmp(b)))
+        .map(|(version, _)| version)
+}
+
+/// Apply a bump mode to a version
+pub(crate) fn update_version(version: &mut semver::Version, bump_mode: &BumpMode) -> Result<()> {
     match bump_mode {
         BumpMode::Patch => {
             version.patch += 1;
@@ -287,8 +1166,195 @@ pub(crate) fn update_version(version: &mut semver::Version, bump_mode: &BumpMode
             version.patch = 0;
         }
         BumpMode::Version(version_str) => {
-            let new_version = semver::Version::parse(version_str).expect("Invalid version");
-            *version = new_version;
+            *version = semver::Version::parse(version_str)
+                .map_err(|e| anyhow::anyhow!("Invalid version `{}`: {}", version_str, e))?;
         }
     }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_occurrences() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        std::fs::write(root.join("Cargo.toml"), "[package]\nversion = \"1.2.3\"\n").unwrap();
+
+        let location = |name: &str, path: &str| LocationPattern {
+            name: name.to_string(),
+            paths: vec![PathBuf::from(path)],
+            patterns: vec![r#"version = "([0-9.]+)""#.into()],
+            ..Default::default()
+        };
+        let (locations, found) = Workflow::init()
+            .root(root.to_path_buf())
+            .unwrap()
+            .locations(vec![
+                location("Cargo", "Cargo.toml"),
+                location("Docker", "Dockerfile"),
+            ])
+            .unwrap()
+            .build()
+            .occurrences()
+            .unwrap();
+
+        assert_eq!(found.len(), 1);
+        assert_eq!((found[0].line, found[0].value.as_str()), (2, "1.2.3"));
+        assert_eq!((locations[0].files, locations[0].matches), (1, 1));
+        assert_eq!((locations[1].files, locations[1].matches), (0, 0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_plugin_runs_once_per_file() {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path();
+        std::fs::write(root.join("service.desc"), b"\xff\x00v1.2.3\x00").unwrap();
+
+        let workflow = Workflow::init()
+            .root(root.to_path_buf())
+            .unwrap()
+            .mode(WorkflowMode::Bump {
+                mode: BumpMode::Minor,
+                version: semver::Version::new(1, 3, 0),
+                counter: None,
+            })
+            .locations(vec![LocationPattern {
+                name: "Descriptor".to_string(),
+                r#type: LocationType::Plugin,
+                plugin: Some(
+                    r#"echo run >> runs.txt; echo '[{"start": 3, "end": 8, "new": "1.3.0"}]'"#
+                        .to_string(),
+                ),
+                paths: vec![PathBuf::from("*.desc")],
+                ..Default::default()
+            }])
+            .unwrap()
+            .build();
+        workflow.covered().unwrap();
+        let changes = workflow.plan().unwrap();
+        let mut content = std::fs::read(root.join("service.desc")).unwrap();
+        let runs = std::fs::read_to_string(root.join("runs.txt")).unwrap();
+
+        assert_eq!(runs.lines().count(), 1);
+        apply_replacements(&mut content, &changes[0].replacements);
+        assert_eq!(content, b"\xff\x00v1.3.0\x00");
+    }
+
+    #[test]
+    fn test_capture_groups_unnamed() {
+        let regex = regex::Regex::new(r#"version = "([0-9.]+)""#).unwrap();
+        let capture = regex.captures(r#"version = "1.2.3""#).unwrap();
+        let groups = capture_groups(&capture);
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].0, VersionComponent::Version);
+        assert_eq!(groups[0].1.as_str(), "1.2.3");
+    }
+
+    #[test]
+    fn test_capture_groups_named() {
+        let version = semver::Version::new(2, 5, 9);
+        let regex =
+            regex::Regex::new(r"V(?P<major>[0-9]+)_(?P<minor>[0-9]+)_(?P<patch>[0-9]+)").unwrap();
+        let capture = regex.captures("const V1_2_3: u8 = 0;").unwrap();
+        let groups = capture_groups(&capture);
+
+        let rendered: Vec<String> = groups.iter().map(|(c, _)| c.render(&version)).collect();
+        assert_eq!(rendered, vec!["2", "5", "9"]);
+    }
+
+    #[test]
+    fn test_render_dates() {
+        let date = chrono::DateTime::parse_from_rfc3339("2025-03-04T05:06:07Z")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        let workflow = Workflow::init().date(Some(date)).build();
+        let version = semver::Version::new(1, 2, 3);
+
+        let mut location = LocationPattern {
+            r#type: LocationType::Year,
+            ..Default::default()
+        };
+        let render = |location: &LocationPattern| {
+            workflow
+                .render(location, 0, VersionComponent::Version, "", &version)
+                .unwrap()
+        };
+        assert_eq!(render(&location), "2025");
+
+        location.r#type = LocationType::Date;
+        assert_eq!(render(&location), "2025-03-04");
+
+        location.r#type = LocationType::Timestamp;
+        assert_eq!(render(&location), "2025-03-04T05:06:07Z");
+
+        location.format = Some("%d/%m/%Y".to_string());
+        assert_eq!(render(&location), "04/03/2025");
+
+        let invalid = LocationPattern {
+            format: Some("%Q".to_string()),
+            ..location.clone()
+        };
+        assert!(
+            workflow
+                .render(&invalid, 0, VersionComponent::Version, "", &version)
+                .is_err()
+        );
+
+        location.r#type = LocationType::Version;
+        assert_eq!(render(&location), "1.2.3");
+
+        location.patterns = vec!["(.*)".into()];
+        location.script = Some(r#"`${version.major}.x`"#.to_string());
+        location.compile().unwrap();
+        assert_eq!(render(&location), "1.x");
+    }
+
+    #[test]
+    fn test_update_version() {
+        let mut version = semver::Version::new(1, 2, 3);
+        update_version(&mut version, &BumpMode::Minor).unwrap();
+        assert_eq!(version, semver::Version::new(1, 3, 0));
+        update_version(&mut version, &BumpMode::Version("2.0.0".to_string())).unwrap();
+        assert_eq!(version, semver::Version::new(2, 0, 0));
+        assert!(update_version(&mut version, &BumpMode::Version("foo".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_evaluate_counter() {
+        let version = semver::Version::new(1, 4, 2);
+        assert_eq!(
+            evaluate_counter("{major}*10000+{minor}*100+{patch}", &version, None).unwrap(),
+            10402
+        );
+        assert_eq!(
+            evaluate_counter("{counter} + 1000", &version, Some(7)).unwrap(),
+            1007
+        );
+        assert!(evaluate_counter("{counter}", &version, None).is_err());
+        assert!(evaluate_counter("{major} - 1", &version, None).is_err());
+    }
+
+    #[test]
+    fn test_common_version() {
+        let found = |values: &[&str]| -> Vec<(PathBuf, String)> {
+            values
+                .iter()
+                .map(|v| (PathBuf::from("Cargo.toml"), v.to_string()))
+                .collect()
+        };
+        assert_eq!(
+            common_version(&found(&["1.2.3", "1.2.3", "1.3.0"])),
+            Some(semver::Version::new(1, 2, 3))
+        );
+        assert_eq!(
+            common_version(&found(&["1.2.3", "2.0.0", "not-a-version"])),
+            Some(semver::Version::new(2, 0, 0))
+        );
+        assert_eq!(common_version(&found(&[])), None);
+    }
 }
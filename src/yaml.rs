@@ -0,0 +1,356 @@
+//! Edits of a YAML document which keep its comments and formatting.
+//!
+//! The configuration file is edited line by line instead of being parsed and
+//! written again, so everything not touched by an edit stays as it was.
+//! Mappings have to be in block style, sequences can be in block or flow
+//! style (`excludes: [a, b]`).
+use anyhow::{Result, anyhow, bail};
+
+/// Lines of a YAML document
+#[derive(Debug, Clone)]
+pub struct Document {
+    lines: Vec<String>,
+}
+
+/// Mapping in a document: the lines `start..end` and the column of its keys
+#[derive(Debug, Clone, Copy)]
+pub struct Scope {
+    start: usize,
+    end: usize,
+    column: usize,
+}
+
+impl Document {
+    /// Split a document into lines
+    pub fn new(content: &str) -> Self {
+        Self {
+            lines: content.lines().map(str::to_string).collect(),
+        }
+    }
+
+    /// Top-level mapping of the document
+    pub fn root(&self) -> Scope {
+        Scope {
+            start: 0,
+            end: self.lines.len(),
+            column: 0,
+        }
+    }
+
+    /// Mapping at `key` in a scope, the key is added when it is missing
+    pub fn mapping(&mut self, scope: Scope, key: &str) -> Result<Scope> {
+        let Some(line) = self.find_key(scope, key) else {
+            let at = self.insert_point(scope);
+            self.lines
+                .insert(at, format!("{}{}:", indent(scope.column), scalar(key)?));
+            return Ok(Scope {
+                start: at + 1,
+                end: at + 1,
+                column: scope.column + 2,
+            });
+        };
+        if !self.value(line, scope.column).is_empty() {
+            bail!("`{}` is not a block mapping (line {})", key, line + 1);
+        }
+        let (start, end) = self.value_block(line, scope.column);
+        let column = self
+            .first_content(start, end)
+            .map(|first| indentation(&self.lines[first]))
+            .unwrap_or(scope.column + 2);
+        Ok(Scope { start, end, column })
+    }
+
+    /// Mapping item of the sequence at `key` with `field` set to `value`
+    pub fn item(&self, scope: Scope, key: &str, field: &str, value: &str) -> Option<Scope> {
+        let line = self.find_key(scope, key)?;
+        if !self.value(line, scope.column).is_empty() {
+            return None;
+        }
+        let (start, end) = self.value_block(line, scope.column);
+        let first = self.first_content(start, end)?;
+        let column = indentation(&self.lines[first]);
+
+        let items: Vec<usize> = (start..end)
+            .filter(|&i| is_content(&self.lines[i]) && indentation(&self.lines[i]) == column)
+            .filter(|&i| self.lines[i].trim_start().starts_with('-'))
+            .collect();
+        items.iter().enumerate().find_map(|(index, &item)| {
+            let text = self.lines[item][column + 1..].trim_start();
+            let item = Scope {
+                start: item,
+                end: items.get(index + 1).copied().unwrap_or(end),
+                column: self.lines[item].len() - text.len(),
+            };
+            let line = self.find_key(item, field)?;
+            let found = serde_yaml::from_str::<String>(self.value(line, item.column)).ok()?;
+            (found == value).then_some(item)
+        })
+    }
+
+    /// Append items to the sequence at `key` in a scope, the key is added
+    /// when it is missing. Items are YAML values, which can span lines.
+    pub fn append(&mut self, scope: Scope, key: &str, items: &[String]) -> Result<()> {
+        let Some(line) = self.find_key(scope, key) else {
+            let at = self.insert_point(scope);
+            let mut lines = vec![format!("{}{}:", indent(scope.column), scalar(key)?)];
+            lines.extend(
+                items
+                    .iter()
+                    .flat_map(|item| sequence_item(item, scope.column + 2)),
+            );
+            self.lines.splice(at..at, lines);
+            return Ok(());
+        };
+
+        let value = self.value(line, scope.column).to_string();
+        if value.starts_with('[') {
+            let comment = value.find(" #").unwrap_or(value.len());
+            let close = value[..comment]
+                .rfind(']')
+                .ok_or_else(|| anyhow!("`{}` is not a sequence (line {})", key, line + 1))?;
+            let mut values = vec![value[1..close].trim().to_string()];
+            values.retain(|v| !v.is_empty());
+            for item in items {
+                if item.contains('\n') {
+                    bail!("`{}` is a flow sequence (line {})", key, line + 1);
+                }
+                values.push(item.clone());
+            }
+            let prefix = self.lines[line].len() - value.len();
+            self.lines[line] = format!(
+                "{}[{}]{}",
+                &self.lines[line][..prefix],
+                values.join(", "),
+                &value[close + 1..]
+            );
+            return Ok(());
+        }
+        if !value.is_empty() {
+            bail!("`{}` is not a sequence (line {})", key, line + 1);
+        }
+
+        let (start, end) = self.value_block(line, scope.column);
+        let column = self
+            .first_content(start, end)
+            .map(|first| indentation(&self.lines[first]))
+            .unwrap_or(scope.column + 2);
+        let at = self
+            .last_content(start, end)
+            .map_or(line + 1, |last| last + 1);
+        let lines: Vec<String> = items
+            .iter()
+            .flat_map(|item| sequence_item(item, column))
+            .collect();
+        self.lines.splice(at..at, lines);
+        Ok(())
+    }
+
+    /// Line of a key in a scope
+    fn find_key(&self, scope: Scope, key: &str) -> Option<usize> {
+        (scope.start..scope.end).find(|&i| {
+            text_at(&self.lines[i], scope.column)
+                .and_then(split_key)
+                .is_some_and(|(found, _)| found == key)
+        })
+    }
+
+    /// Value following the key on a line (without a comment)
+    fn value(&self, line: usize, column: usize) -> &str {
+        let (_, value) = text_at(&self.lines[line], column)
+            .and_then(split_key)
+            .unwrap_or_default();
+        if value.starts_with('#') { "" } else { value }
+    }
+
+    /// Lines holding the block value of the key on a line. A sequence can
+    /// start at the column of its key.
+    fn value_block(&self, line: usize, column: usize) -> (usize, usize) {
+        let end = (line + 1..self.lines.len())
+            .find(|&i| {
+                let text = &self.lines[i];
+                is_content(text)
+                    && (indentation(text) < column
+                        || (indentation(text) == column && !text.trim_start().starts_with('-')))
+            })
+            .unwrap_or(self.lines.len());
+        (line + 1, end)
+    }
+
+    /// Line after the last content line of a scope
+    fn insert_point(&self, scope: Scope) -> usize {
+        self.last_content(scope.start, scope.end)
+            .map_or(scope.start, |last| last + 1)
+    }
+
+    fn first_content(&self, start: usize, end: usize) -> Option<usize> {
+        (start..end).find(|&i| is_content(&self.lines[i]))
+    }
+
+    fn last_content(&self, start: usize, end: usize) -> Option<usize> {
+        (start..end).rev().find(|&i| is_content(&self.lines[i]))
+    }
+}
+
+impl std::fmt::Display for Document {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for line in &self.lines {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+/// Render a string as a YAML scalar, quoted when needed
+pub fn scalar(value: &str) -> Result<String> {
+    Ok(serde_yaml::to_string(value)?.trim_end().to_string())
+}
+
+fn indent(column: usize) -> String {
+    " ".repeat(column)
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn is_content(line: &str) -> bool {
+    let text = line.trim();
+    !text.is_empty() && !text.starts_with('#')
+}
+
+/// Text of a line starting at a column, when only indentation or sequence
+/// dashes (`- key: value`) come before it
+fn text_at(line: &str, column: usize) -> Option<&str> {
+    let (before, text) = (line.get(..column)?, line.get(column..)?);
+    (before.chars().all(|c| c == ' ' || c == '-') && !text.starts_with([' ', '-'])).then_some(text)
+}
+
+/// Split `key: value` into the (unquoted) key and the value
+fn split_key(text: &str) -> Option<(String, &str)> {
+    let end = if text.starts_with(['"', '\'']) {
+        text[1..].find(text.chars().next()?)? + 2
+    } else {
+        text.find(": ")
+            .or_else(|| text.strip_suffix(':').map(str::len))?
+    };
+    let value = text[end..].strip_prefix(':')?;
+    if !(value.is_empty() || value.starts_with(' ')) {
+        return None;
+    }
+    let key = serde_yaml::from_str::<String>(&text[..end]).ok()?;
+    Some((key, value.trim()))
+}
+
+/// Lines of a sequence item at a column
+fn sequence_item(item: &str, column: usize) -> Vec<String> {
+    item.lines()
+        .enumerate()
+        .map(|(index, line)| match index {
+            0 => format!("{}- {}", indent(column), line),
+            _ if line.is_empty() => String::new(),
+            _ => format!("{}  {}", indent(column), line),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = "# Project configuration
+version: 1.0.0 # current release
+
+locations:
+  # Documentation
+  - name: Docs
+    paths: ['**/*.md']
+    excludes:
+      - CHANGELOG.md # history
+  - name: \"Helm Chart\"
+    excludes: [charts/old/Chart.yaml]
+defaults:
+  # Tweaks of the default locations
+  overrides:
+    Rust Cargo:
+      required: true
+";
+
+    #[test]
+    fn test_append() {
+        let mut document = Document::new(CONFIG);
+        let root = document.root();
+        let docs = document.item(root, "locations", "name", "Docs").unwrap();
+        document
+            .append(docs, "excludes", &["docs/example.md".to_string()])
+            .unwrap();
+        let helm = document
+            .item(document.root(), "locations", "name", "Helm Chart")
+            .unwrap();
+        document
+            .append(helm, "excludes", &["charts/new/Chart.yaml".to_string()])
+            .unwrap();
+        assert!(
+            document
+                .item(document.root(), "locations", "name", "Missing")
+                .is_none()
+        );
+
+        let defaults = document.mapping(document.root(), "defaults").unwrap();
+        let overrides = document.mapping(defaults, "overrides").unwrap();
+        let cargo = document.mapping(overrides, "Rust Cargo").unwrap();
+        document
+            .append(cargo, "excludes", &["fixtures/Cargo.toml".to_string()])
+            .unwrap();
+        let defaults = document.mapping(document.root(), "defaults").unwrap();
+        let overrides = document.mapping(defaults, "overrides").unwrap();
+        let docker = document.mapping(overrides, "Docker").unwrap();
+        document
+            .append(docker, "excludes", &["Dockerfile.dev".to_string()])
+            .unwrap();
+
+        assert_eq!(
+            document.to_string(),
+            "# Project configuration
+version: 1.0.0 # current release
+
+locations:
+  # Documentation
+  - name: Docs
+    paths: ['**/*.md']
+    excludes:
+      - CHANGELOG.md # history
+      - docs/example.md
+  - name: \"Helm Chart\"
+    excludes: [charts/old/Chart.yaml, charts/new/Chart.yaml]
+defaults:
+  # Tweaks of the default locations
+  overrides:
+    Rust Cargo:
+      required: true
+      excludes:
+        - fixtures/Cargo.toml
+    Docker:
+      excludes:
+        - Dockerfile.dev
+"
+        );
+    }
+
+    #[test]
+    fn test_append_missing() {
+        let mut document = Document::new("version: 1.0.0\n");
+        let defaults = document.mapping(document.root(), "defaults").unwrap();
+        let overrides = document.mapping(defaults, "overrides").unwrap();
+        let cargo = document.mapping(overrides, "Rust Cargo").unwrap();
+        document
+            .append(cargo, "excludes", &["a.toml".to_string()])
+            .unwrap();
+
+        assert_eq!(
+            document.to_string(),
+            "version: 1.0.0\ndefaults:\n  overrides:\n    Rust Cargo:\n      excludes:\n        - a.toml\n"
+        );
+        let mut flow = Document::new("defaults: {}\n");
+        assert!(flow.mapping(flow.root(), "defaults").is_err());
+    }
+}